@@ -1,6 +1,7 @@
 use axum::{
     Router,
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    routing::{delete, get, post},
 };
 
 use kc_core::server::ServerState;
@@ -10,5 +11,30 @@ pub mod routes;
 pub fn create_router() -> Router<ServerState> {
     Router::new()
         .route("/mine", get(routes::app::get_mine))
+        .route(
+            "/{id}/rollback/{deployment_id}",
+            post(routes::app::rollback),
+        )
+        .route("/{id}/promote/{deployment_id}", post(routes::app::promote))
+        .route(
+            "/{id}/domains",
+            get(routes::domain::list).post(routes::domain::add),
+        )
+        .route("/{id}/domains/{domain_id}", delete(routes::domain::remove))
+        .route(
+            "/{id}/domains/{domain_id}/verify",
+            post(routes::domain::verify),
+        )
+        .route(
+            "/{id}/aliases",
+            get(routes::alias::list).post(routes::alias::set),
+        )
+        .route("/{id}/aliases/{name}", delete(routes::alias::remove))
+        .route("/deployments/{id}/events", get(routes::deployment::events))
         .route("/deploy", post(routes::deploy::post))
+        .route(
+            "/deploy/upload",
+            // Upload size is enforced while streaming, against the deploy settings
+            post(routes::deploy::upload).layer(DefaultBodyLimit::disable()),
+        )
 }
@@ -0,0 +1,163 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+
+use kc_core::{
+    authentication,
+    deploy::alias_deployment,
+    json::DataJsonResponse,
+    models::{deployment::Deployment, deployment_alias::DeploymentAlias},
+    server::ServerState,
+};
+use reqwest::StatusCode;
+
+use crate::routes::app::find_managed_app;
+
+#[derive(Deserialize, Debug)]
+pub struct SetAliasPayload {
+    name: String,
+    deployment_id: String,
+}
+
+pub async fn list(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    authenticated_claims: authentication::Claims,
+) -> impl IntoResponse {
+    let app = match find_managed_app(&state, &id, &authenticated_claims).await {
+        Ok(app) => app,
+        Err(response) => return response,
+    };
+
+    match DeploymentAlias::find_by_app_id(&state.db_pool, &app.id).await {
+        Ok(aliases) => (
+            StatusCode::OK,
+            Json(DataJsonResponse {
+                data: Some(aliases),
+                error: None,
+            }),
+        ),
+        Err(e) => {
+            eprintln!("[API-App] Error in retrieving aliases: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DataJsonResponse {
+                    error: Some("Error in retrieving aliases".to_string()),
+                    data: None,
+                }),
+            )
+        }
+    }
+}
+
+/// Points an alias to a deployment of the app, creating it or moving it.
+pub async fn set(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    authenticated_claims: authentication::Claims,
+    Json(payload): Json<SetAliasPayload>,
+) -> impl IntoResponse {
+    let app = match find_managed_app(&state, &id, &authenticated_claims).await {
+        Ok(app) => app,
+        Err(response) => return response,
+    };
+
+    let deployment = match Deployment::find_by_id(&state.db_pool, &payload.deployment_id).await {
+        Ok(deployment) if deployment.app_id == app.id => deployment,
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(DataJsonResponse {
+                    error: Some("Deployment not found".to_string()),
+                    data: None,
+                }),
+            );
+        }
+        Err(e) => {
+            println!("[API-App] Deployment not found: {}", e);
+            return (
+                StatusCode::NOT_FOUND,
+                Json(DataJsonResponse {
+                    error: Some("Deployment not found".to_string()),
+                    data: None,
+                }),
+            );
+        }
+    };
+
+    match alias_deployment(&state.db_pool, &app, &deployment, &payload.name).await {
+        Ok(alias) => (
+            StatusCode::OK,
+            Json(DataJsonResponse {
+                data: Some(alias),
+                error: None,
+            }),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        ),
+    }
+}
+
+pub async fn remove(
+    State(state): State<ServerState>,
+    Path((id, name)): Path<(String, String)>,
+    authenticated_claims: authentication::Claims,
+) -> impl IntoResponse {
+    let app = match find_managed_app(&state, &id, &authenticated_claims).await {
+        Ok(app) => app,
+        Err(response) => return response,
+    };
+
+    let alias = match DeploymentAlias::find_by_name(&state.db_pool, &app.id, &name).await {
+        Ok(Some(alias)) => alias,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(DataJsonResponse {
+                    error: Some("Alias not found".to_string()),
+                    data: None,
+                }),
+            );
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DataJsonResponse {
+                    error: Some(e),
+                    data: None,
+                }),
+            );
+        }
+    };
+
+    match alias.delete(&state.db_pool).await {
+        Ok(alias) => {
+            println!(
+                "[API-App] Alias \"{}\" removed from app \"{}\"",
+                alias.name, app.name
+            );
+            (
+                StatusCode::OK,
+                Json(DataJsonResponse {
+                    data: Some(alias),
+                    error: None,
+                }),
+            )
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        ),
+    }
+}
@@ -1,7 +1,18 @@
-use axum::{Json, extract::State, response::IntoResponse};
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
 
-use kc_core::{authentication, json::DataJsonResponse, models::app::App, server::ServerState};
+use kc_core::{
+    authentication,
+    deploy::{promote_deployment, rollback_app},
+    json::DataJsonResponse,
+    models::{app::App, deployment::Deployment},
+    server::ServerState,
+};
 use reqwest::StatusCode;
+use serde::Serialize;
 
 pub async fn get_mine(
     State(state): State<ServerState>,
@@ -27,3 +38,132 @@ pub async fn get_mine(
         }
     }
 }
+
+pub async fn rollback(
+    State(state): State<ServerState>,
+    Path((id, deployment_id)): Path<(String, String)>,
+    authenticated_claims: authentication::Claims,
+) -> impl IntoResponse {
+    let (app, source) =
+        match find_managed_deployment(&state, &id, &deployment_id, &authenticated_claims).await {
+            Ok(found) => found,
+            Err(response) => return response,
+        };
+
+    match rollback_app(&state, &app, &source).await {
+        Ok(deployment) => (
+            StatusCode::OK,
+            Json(DataJsonResponse {
+                data: Some(deployment),
+                error: None,
+            }),
+        ),
+        Err(e) => {
+            eprintln!("[API-App] Rollback failed: {}", e);
+            (
+                StatusCode::BAD_REQUEST,
+                Json(DataJsonResponse {
+                    error: Some(e),
+                    data: None,
+                }),
+            )
+        }
+    }
+}
+
+pub async fn promote(
+    State(state): State<ServerState>,
+    Path((id, deployment_id)): Path<(String, String)>,
+    authenticated_claims: authentication::Claims,
+) -> impl IntoResponse {
+    let (app, preview) =
+        match find_managed_deployment(&state, &id, &deployment_id, &authenticated_claims).await {
+            Ok(found) => found,
+            Err(response) => return response,
+        };
+
+    match promote_deployment(&state, &app, &preview).await {
+        Ok(deployment) => (
+            StatusCode::OK,
+            Json(DataJsonResponse {
+                data: Some(deployment),
+                error: None,
+            }),
+        ),
+        Err(e) => {
+            eprintln!("[API-App] Promotion failed: {}", e);
+            (
+                StatusCode::BAD_REQUEST,
+                Json(DataJsonResponse {
+                    error: Some(e),
+                    data: None,
+                }),
+            )
+        }
+    }
+}
+
+type DeploymentResponse = (StatusCode, Json<DataJsonResponse<Deployment>>);
+
+/// Loads an app, if the user is allowed to manage it.
+pub(crate) async fn find_managed_app<T: Serialize>(
+    state: &ServerState,
+    id: &String,
+    claims: &authentication::Claims,
+) -> Result<App, (StatusCode, Json<DataJsonResponse<T>>)> {
+    let app = match App::find_by_id(&state.db_pool, id).await {
+        Ok(app) => app,
+        Err(e) => {
+            println!("[API-App] App not found: {}", e);
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(DataJsonResponse {
+                    error: Some("App not found".to_string()),
+                    data: None,
+                }),
+            ));
+        }
+    };
+
+    match app.is_managed_by(&state.db_pool, claims).await {
+        Ok(true) => Ok(app),
+        Ok(false) => Err((
+            StatusCode::FORBIDDEN,
+            Json(DataJsonResponse {
+                error: Some("Insufficient permissions to manage this app".to_string()),
+                data: None,
+            }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        )),
+    }
+}
+
+/// Loads an app and one of its deployments, if the user is allowed to manage the app.
+async fn find_managed_deployment(
+    state: &ServerState,
+    id: &String,
+    deployment_id: &String,
+    claims: &authentication::Claims,
+) -> Result<(App, Deployment), DeploymentResponse> {
+    let app = find_managed_app(state, id, claims).await?;
+
+    match Deployment::find_by_id(&state.db_pool, deployment_id).await {
+        Ok(deployment) => Ok((app, deployment)),
+        Err(e) => {
+            println!("[API-App] Deployment not found: {}", e);
+            Err((
+                StatusCode::NOT_FOUND,
+                Json(DataJsonResponse {
+                    error: Some("Deployment not found".to_string()),
+                    data: None,
+                }),
+            ))
+        }
+    }
+}
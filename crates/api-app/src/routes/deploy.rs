@@ -1,115 +1,459 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
-use reqwest::{Client, multipart};
+use axum::{
+    Json,
+    body::{Body, to_bytes},
+    extract::{
+        State,
+        multipart::{Field, Multipart},
+    },
+    http::StatusCode,
+    response::IntoResponse,
+};
+use base64::prelude::{BASE64_STANDARD, Engine};
 use serde::{Deserialize, Serialize};
-use tokio::fs;
+use sqlx::types::Uuid;
+use std::path::{Path, PathBuf};
+use tokio::{fs, io::AsyncWriteExt};
 
 use kc_core::{
     database::DbPool,
+    deploy::{DeployConfig, start_publication},
+    ipfs::find_or_create_ipns_key,
     json::DataJsonResponse,
-    models::node::Node,
     models::{
         app::App,
         deployment::{Deployment, DeploymentStatus},
-        deployment_node::{DeploymentNode, PinStatus},
     },
     payloads::{
         app::{CreateAppPayload, UpdateAppPayload},
         deployment::{CreateDeploymentPayload, UpdateDeploymentPayload},
-        deployment_node::{CreateDeploymentNodePayload, UpdateDeploymentNodePayload},
     },
+    replication::validate_replication_factor,
     server::ServerState,
+    site_rules::load_site_rules,
+    utils::archive::{ArchiveError, UnpackSummary, unpack_archive},
 };
-use std::collections::HashMap;
+
+// Room left in the JSON deploy body for the fields around the archive
+const JSON_BODY_OVERHEAD: usize = 64 * 1024;
+
+// Metadata fields of multipart uploads are short identifiers
+const MAX_TEXT_FIELD_SIZE: usize = 1024;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AppDeployPayload {
     id: Option<String>,
     team_id: Option<String>,
     name: Option<String>,
-    content: String,
+    content: Option<String>,
+    archive: Option<String>,
+    replication_factor: Option<i32>,
+    preview: Option<bool>,
 }
 
+/// Live deploys answer with the app, previews also tell where to look at the build.
 #[derive(Serialize, Debug)]
-pub struct NodeDeployPayload {
-    name: String,
-    cid: String,
-}
-
-#[derive(Deserialize, Debug)]
-struct IPFSAddResponse {
-    #[serde(rename = "Hash")]
-    hash: String,
+#[serde(untagged)]
+pub enum DeployOutcome {
+    Live(App),
+    Preview {
+        app: App,
+        deployment: Deployment,
+        preview_url: String,
+    },
 }
 
-#[derive(Deserialize, Debug, Clone)]
-struct KeyInfo {
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "Id")]
-    id: String,
-}
+pub async fn post(State(state): State<ServerState>, body: Body) -> impl IntoResponse {
+    // Archives are sent base64 encoded, so allow for the encoding overhead
+    let deploy_config = &state.server_settings.deploy;
+    let body_limit = (deploy_config.max_archive_size as usize).div_ceil(3) * 4 + JSON_BODY_OVERHEAD;
+    let payload = match to_bytes(body, body_limit).await {
+        Ok(bytes) => match serde_json::from_slice::<AppDeployPayload>(&bytes) {
+            Ok(payload) => payload,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(DataJsonResponse {
+                        error: Some(format!("Invalid deploy payload: {}", e)),
+                        data: None,
+                    }),
+                );
+            }
+        },
+        Err(_) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(DataJsonResponse {
+                    error: Some(format!(
+                        "Archive is larger than {} bytes",
+                        deploy_config.max_archive_size
+                    )),
+                    data: None,
+                }),
+            );
+        }
+    };
 
-#[derive(Deserialize, Debug)]
-struct KeyListResponse {
-    #[serde(rename = "Keys")]
-    keys: Vec<KeyInfo>,
-}
+    // Unpack content in a working directory dedicated to this deployment
+    let work_dir = PathBuf::from(&deploy_config.working_dir).join(Uuid::new_v4().to_string());
+    let site_dir = work_dir.join("site");
+    let result = match stage_payload(&payload, &work_dir, &site_dir, deploy_config).await {
+        Ok(summary) => deploy_site(&state, &payload, &site_dir, summary).await,
+        Err(e) => Err(e),
+    };
+    remove_work_dir(&work_dir).await;
 
-#[derive(Deserialize, Debug)]
-struct IpnsPublishResponse {
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "Value")]
-    value: String,
+    deploy_response(result)
 }
 
-pub async fn post(
+pub async fn upload(
     State(state): State<ServerState>,
-    Json(payload): Json<AppDeployPayload>,
+    mut multipart: Multipart,
 ) -> impl IntoResponse {
-    // Temporary store file
-    let tmp_path = "/tmp/keystone_deploy.tmp";
-    if let Err(e) = fs::write(tmp_path, &payload.content).await {
-        eprintln!("[API-App] Error in file creation: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
+    let deploy_config = &state.server_settings.deploy;
+    let work_dir = PathBuf::from(&deploy_config.working_dir).join(Uuid::new_v4().to_string());
+    let site_dir = work_dir.join("site");
+    let result = match receive_upload(&mut multipart, &work_dir, &site_dir, deploy_config).await {
+        Ok((payload, summary)) => deploy_site(&state, &payload, &site_dir, summary).await,
+        Err(e) => Err(e),
+    };
+    remove_work_dir(&work_dir).await;
+
+    deploy_response(result)
+}
+
+fn deploy_response(
+    result: Result<DeployOutcome, (StatusCode, String)>,
+) -> (StatusCode, Json<DataJsonResponse<DeployOutcome>>) {
+    match result {
+        Ok(outcome) => (
+            StatusCode::OK,
+            Json(DataJsonResponse {
+                data: Some(outcome),
+                error: None,
+            }),
+        ),
+        Err((status, e)) => (
+            status,
             Json(DataJsonResponse {
-                error: Some("Error in file creation".to_string()),
+                error: Some(e),
                 data: None,
             }),
-        );
+        ),
+    }
+}
+
+async fn remove_work_dir(work_dir: &Path) {
+    if let Err(e) = fs::remove_dir_all(work_dir).await {
+        eprintln!("[API-App] Error in working directory cleanup: {}", e);
     }
+}
+
+async fn receive_upload(
+    multipart: &mut Multipart,
+    work_dir: &Path,
+    site_dir: &Path,
+    deploy_config: &DeployConfig,
+) -> Result<(AppDeployPayload, UnpackSummary), (StatusCode, String)> {
+    if let Err(e) = fs::create_dir_all(site_dir).await {
+        eprintln!("[API-App] Error in working directory creation: {}", e);
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error in file creation".to_string(),
+        ));
+    }
+
+    let mut payload = AppDeployPayload {
+        id: None,
+        team_id: None,
+        name: None,
+        content: None,
+        archive: None,
+        replication_factor: None,
+        preview: None,
+    };
+    let mut summary = None;
+
+    loop {
+        let mut field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return Err((e.status(), e.body_text())),
+        };
+
+        match field.name() {
+            Some("id") => payload.id = Some(read_text_field(&mut field).await?),
+            Some("team_id") => payload.team_id = Some(read_text_field(&mut field).await?),
+            Some("name") => payload.name = Some(read_text_field(&mut field).await?),
+            Some("preview") => {
+                let value = read_text_field(&mut field).await?;
+                match value.trim().parse::<bool>() {
+                    Ok(preview) => payload.preview = Some(preview),
+                    Err(_) => {
+                        return Err((
+                            StatusCode::BAD_REQUEST,
+                            "preview must be true or false".to_string(),
+                        ));
+                    }
+                }
+            }
+            Some("replication_factor") => {
+                let value = read_text_field(&mut field).await?;
+                match value.trim().parse::<i32>() {
+                    Ok(factor) => payload.replication_factor = Some(factor),
+                    Err(_) => {
+                        return Err((
+                            StatusCode::BAD_REQUEST,
+                            "replication_factor must be an integer".to_string(),
+                        ));
+                    }
+                }
+            }
+            Some("archive") | Some("file") if summary.is_some() => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Only one archive or file can be uploaded".to_string(),
+                ));
+            }
+            Some("archive") => {
+                let archive_path = work_dir.join("archive");
+                stream_field_to_file(&mut field, &archive_path, deploy_config.max_archive_size)
+                    .await?;
+                summary = Some(unpack_site(archive_path, site_dir, deploy_config).await?);
+            }
+            Some("file") => {
+                // Only keep the last component so the file always lands in the site root
+                let file_name = field
+                    .file_name()
+                    .and_then(|name| Path::new(name).file_name())
+                    .map(|name| name.to_os_string())
+                    .unwrap_or_else(|| "index.html".into());
+                let total_size = stream_field_to_file(
+                    &mut field,
+                    &site_dir.join(file_name),
+                    deploy_config.max_unpacked_size,
+                )
+                .await?;
+                summary = Some(UnpackSummary {
+                    file_count: 1,
+                    total_size,
+                });
+            }
+            _ => continue,
+        }
+    }
+
+    match summary {
+        Some(summary) => Ok((payload, summary)),
+        None => Err((
+            StatusCode::BAD_REQUEST,
+            "archive or file field is required".to_string(),
+        )),
+    }
+}
+
+async fn read_text_field(field: &mut Field<'_>) -> Result<String, (StatusCode, String)> {
+    let mut value = Vec::new();
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|e| (e.status(), e.body_text()))?
+    {
+        value.extend_from_slice(&chunk);
+        if value.len() > MAX_TEXT_FIELD_SIZE {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Form field is larger than {} bytes", MAX_TEXT_FIELD_SIZE),
+            ));
+        }
+    }
+
+    String::from_utf8(value).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Form field is not valid UTF-8".to_string(),
+        )
+    })
+}
+
+async fn stream_field_to_file(
+    field: &mut Field<'_>,
+    path: &Path,
+    max_size: u64,
+) -> Result<u64, (StatusCode, String)> {
+    let mut file = match fs::File::create(path).await {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("[API-App] Error in file creation: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error in file creation".to_string(),
+            ));
+        }
+    };
+
+    let mut written: u64 = 0;
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|e| (e.status(), e.body_text()))?
+    {
+        written += chunk.len() as u64;
+        if written > max_size {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Upload is larger than {} bytes", max_size),
+            ));
+        }
+
+        if let Err(e) = file.write_all(&chunk).await {
+            eprintln!("[API-App] Error in file write: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error in file write".to_string(),
+            ));
+        }
+    }
+
+    if let Err(e) = file.flush().await {
+        eprintln!("[API-App] Error in file write: {}", e);
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error in file write".to_string(),
+        ));
+    }
+
+    Ok(written)
+}
+
+async fn stage_payload(
+    payload: &AppDeployPayload,
+    work_dir: &Path,
+    site_dir: &Path,
+    deploy_config: &DeployConfig,
+) -> Result<UnpackSummary, (StatusCode, String)> {
+    if let Err(e) = fs::create_dir_all(site_dir).await {
+        eprintln!("[API-App] Error in working directory creation: {}", e);
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error in file creation".to_string(),
+        ));
+    }
+
+    match (&payload.archive, &payload.content) {
+        (Some(archive), _) => {
+            let data = match BASE64_STANDARD.decode(archive) {
+                Ok(data) => data,
+                Err(e) => {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        format!("Archive is not valid base64: {}", e),
+                    ));
+                }
+            };
+
+            let archive_path = work_dir.join("archive");
+            if let Err(e) = fs::write(&archive_path, data).await {
+                eprintln!("[API-App] Error in file creation: {}", e);
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Error in file creation".to_string(),
+                ));
+            }
+
+            unpack_site(archive_path, site_dir, deploy_config).await
+        }
+        (None, Some(content)) => {
+            if let Err(e) = fs::write(site_dir.join("index.html"), content).await {
+                eprintln!("[API-App] Error in file creation: {}", e);
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Error in file creation".to_string(),
+                ));
+            }
+
+            Ok(UnpackSummary {
+                file_count: 1,
+                total_size: content.len() as u64,
+            })
+        }
+        (None, None) => Err((
+            StatusCode::BAD_REQUEST,
+            "content or archive is required".to_string(),
+        )),
+    }
+}
+
+async fn unpack_site(
+    archive_path: PathBuf,
+    site_dir: &Path,
+    deploy_config: &DeployConfig,
+) -> Result<UnpackSummary, (StatusCode, String)> {
+    match unpack_archive(archive_path, site_dir.to_path_buf(), deploy_config.clone()).await {
+        Ok(summary) if summary.file_count == 0 => Err((
+            StatusCode::BAD_REQUEST,
+            "Archive contains no files".to_string(),
+        )),
+        Ok(summary) => {
+            println!(
+                "[API-App] Archive unpacked: {} files, {} bytes",
+                summary.file_count, summary.total_size
+            );
+            Ok(summary)
+        }
+        Err(e) => {
+            eprintln!("[API-App] Archive unpacking failed: {}", e);
+            let status = match e {
+                ArchiveError::ArchiveTooLarge(_)
+                | ArchiveError::UnpackedTooLarge(_)
+                | ArchiveError::TooManyFiles(_) => StatusCode::PAYLOAD_TOO_LARGE,
+                ArchiveError::UnsupportedFormat
+                | ArchiveError::UnsafePath(_)
+                | ArchiveError::Zip(_) => StatusCode::BAD_REQUEST,
+                ArchiveError::Io(_) | ArchiveError::TaskFailed(_) => {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            };
+            Err((status, e.to_string()))
+        }
+    }
+}
+
+async fn deploy_site(
+    state: &ServerState,
+    payload: &AppDeployPayload,
+    site_dir: &Path,
+    summary: UnpackSummary,
+) -> Result<DeployOutcome, (StatusCode, String)> {
+    if let Some(factor) = payload.replication_factor
+        && let Err(e) = validate_replication_factor(&state.server_settings.replication, factor)
+    {
+        return Err((StatusCode::BAD_REQUEST, e));
+    }
+
+    // Site config files are checked before anything is created
+    let site_rules = match load_site_rules(site_dir).await {
+        Ok(rules) => (!rules.is_empty()).then_some(rules),
+        Err(e) => return Err((StatusCode::BAD_REQUEST, e)),
+    };
 
     // Find or create app in database
-    let app = match find_or_create_app(&state.db_pool, &payload).await {
+    let app = match find_or_create_app(&state.db_pool, payload).await {
         Ok(app) => app,
         Err(e) => {
             eprintln!("[API-App] {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DataJsonResponse {
-                    error: Some(format!("{}", e)),
-                    data: None,
-                }),
-            );
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e));
         }
     };
 
     // Deploy app to IPFS
-    let cid = match add_to_ipfs(&state.server_settings.server.ipfs_host, tmp_path).await {
+    let cid = match state.ipfs.add(site_dir).await {
         Ok(cid) => {
-            println!("[API-App] File added to IPFS. CID: {}", cid);
+            println!("[API-App] Site added to IPFS. CID: {}", cid);
             cid
         }
         Err(e) => {
             eprintln!("[API-App] Add to IPFS failed: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DataJsonResponse {
-                    error: Some(e.to_string()),
-                    data: None,
-                }),
-            );
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
         }
     };
     let deployment = match Deployment::create(
@@ -117,6 +461,10 @@ pub async fn post(
         &CreateDeploymentPayload {
             app_id: app.id.to_string(),
             cid: cid.clone(),
+            file_count: summary.file_count as i64,
+            total_size: summary.total_size as i64,
+            source_deployment_id: None,
+            site_rules,
         },
     )
     .await
@@ -124,34 +472,24 @@ pub async fn post(
         Ok(deployment) => deployment,
         Err(e) => {
             eprintln!("[API-App] Error in database deployment creation: {}", e);
-            return (
+            return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DataJsonResponse {
-                    error: Some("Error in deployment creation".to_string()),
-                    data: None,
-                }),
-            );
+                "Error in deployment creation".to_string(),
+            ));
         }
     };
 
     // Find or create IPNS key
-    let key_info =
-        match find_or_create_ipns_key(&state.server_settings.server.ipfs_host, &app.name).await {
-            Ok(info) => {
-                println!("[API-App] IPNS key: {}", info.id);
-                info
-            }
-            Err(e) => {
-                eprintln!("[API-App] IPNS management failed: {}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(DataJsonResponse {
-                        error: Some(e.to_string()),
-                        data: None,
-                    }),
-                );
-            }
-        };
+    let key_info = match find_or_create_ipns_key(state.ipfs.as_ref(), &app.name).await {
+        Ok(info) => {
+            println!("[API-App] IPNS key: {}", info.id);
+            info
+        }
+        Err(e) => {
+            eprintln!("[API-App] IPNS management failed: {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e));
+        }
+    };
     let app = match app
         .update(
             &state.db_pool,
@@ -160,6 +498,7 @@ pub async fn post(
                 name: None,
                 key_name: Some(key_info.name.clone()),
                 ipns_name: None,
+                replication_factor: payload.replication_factor,
             },
         )
         .await
@@ -167,196 +506,51 @@ pub async fn post(
         Ok(app) => app,
         Err(e) => {
             eprintln!("[API-App] Error in database app update: {}", e);
-            return (
+            return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DataJsonResponse {
-                    error: Some(format!("Error in app update: {}", e)),
-                    data: None,
-                }),
-            );
+                format!("Error in app update: {}", e),
+            ));
         }
     };
 
-    // Deploy to IPNS in background task
-    let deployment = match deployment
-        .update(
-            &state.db_pool,
-            &UpdateDeploymentPayload {
-                app_id: None,
-                cid: None,
-                status: Some(DeploymentStatus::PUBLISHING),
-            },
-        )
-        .await
-    {
-        Ok(deployment) => deployment,
-        Err(e) => {
-            eprintln!("[API-App] Error in database deployment update: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DataJsonResponse {
-                    error: Some(format!("Error in deployment update: {}", e)),
-                    data: None,
-                }),
-            );
-        }
-    };
-    let db_pool_clone = state.db_pool.clone();
-    let app_clone = app.clone();
-    let ipfs_host_clone = state.server_settings.server.ipfs_host.clone();
-    let key_name_clone = key_info.name.clone();
-    let cid_clone = cid.clone();
-    let deployment_clone = deployment.clone();
-    tokio::spawn(async move {
-        match publish_to_ipns(&ipfs_host_clone, &key_name_clone, &cid_clone).await {
-            Ok(ipns_result) => {
-                println!(
-                    "[API-App] App \"{}\" published on IPNS ({} -> {})",
-                    app_clone.name, ipns_result.name, ipns_result.value
-                );
-                let _ = app_clone
-                    .update(
-                        &db_pool_clone,
-                        &UpdateAppPayload {
-                            team_id: None,
-                            name: None,
-                            key_name: None,
-                            ipns_name: Some(ipns_result.name.clone()),
-                        },
-                    )
-                    .await;
-                let _ = deployment_clone
-                    .update(
-                        &db_pool_clone,
-                        &UpdateDeploymentPayload {
-                            app_id: None,
-                            cid: None,
-                            status: Some(DeploymentStatus::DEPLOYED),
-                        },
-                    )
-                    .await;
-            }
-            Err(e) => {
-                eprintln!("[API-App] IPNS publication failed: {}", e);
-                let _ = deployment_clone
-                    .update(
-                        &db_pool_clone,
-                        &UpdateDeploymentPayload {
-                            app_id: None,
-                            cid: None,
-                            status: Some(DeploymentStatus::FAILED),
-                        },
-                    )
-                    .await;
-            }
-        };
-    });
-
-    let client = Client::new();
-    let nodes_to_deploy = match select_nodes_deployable(&state).await {
-        Ok(nodes_map) => {
-            if nodes_map.is_empty() {
-                println!("[API-App] App deployed, but no active node found to pin it.");
-            }
-            nodes_map
-        }
-        Err(e) => {
-            eprintln!("[API-App] Error in retrieving nodes: {}.", e);
-            HashMap::new()
-        }
-    };
-
-    for (id, node) in nodes_to_deploy {
-        let client_clone = client.clone();
-        let deploy_url = format!("http://{}:{}/api/deploy", node.ip, node.port);
-        let node_payload = NodeDeployPayload {
-            name: app.name.clone(),
-            cid: cid.clone(),
-        };
-        let state_clone = state.clone();
-        let deployment_clone = deployment.clone();
-
-        tokio::spawn(async move {
-            let deployment_node = match DeploymentNode::create(
-                &state_clone.db_pool,
-                &CreateDeploymentNodePayload {
-                    deployment_id: deployment_clone.id.to_string(),
-                    node_id: id.clone(),
+    if payload.preview.unwrap_or(false) {
+        let deployment = match deployment
+            .update(
+                &state.db_pool,
+                &UpdateDeploymentPayload {
+                    app_id: None,
+                    cid: None,
+                    status: Some(DeploymentStatus::PREVIEW),
                 },
             )
             .await
-            {
-                Ok(deployment_node) => deployment_node,
-                Err(e) => {
-                    eprintln!("[API-App] Error creating deployment_node record: {}", e);
-                    return;
-                }
-            };
-
-            match client_clone
-                .post(&deploy_url)
-                .json(&node_payload)
-                .send()
-                .await
-            {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        println!("[API-App] Send app deployment to node: id={}", id);
-                        let _ = deployment_node
-                            .update(
-                                &state_clone.db_pool,
-                                &UpdateDeploymentNodePayload {
-                                    deployment_id: None,
-                                    node_id: None,
-                                    status: Some(PinStatus::PINNED),
-                                },
-                            )
-                            .await;
-                    } else {
-                        println!(
-                            "[API-App] Failed to app deployment to node: id={}, status={}",
-                            id,
-                            response.status()
-                        );
-                        let _ = deployment_node
-                            .update(
-                                &state_clone.db_pool,
-                                &UpdateDeploymentNodePayload {
-                                    deployment_id: None,
-                                    node_id: None,
-                                    status: Some(PinStatus::FAILED),
-                                },
-                            )
-                            .await;
-                    }
-                }
-                Err(e) => {
-                    println!(
-                        "[API-App] Error sending app deployment to node: id={}, error={}",
-                        id, e
-                    );
-                    let _ = deployment_node
-                        .update(
-                            &state_clone.db_pool,
-                            &UpdateDeploymentNodePayload {
-                                deployment_id: None,
-                                node_id: None,
-                                status: Some(PinStatus::FAILED),
-                            },
-                        )
-                        .await;
-                }
+        {
+            Ok(deployment) => deployment,
+            Err(e) => {
+                eprintln!("[API-App] Error in database deployment update: {}", e);
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Error in deployment update: {}", e),
+                ));
             }
+        };
+
+        println!("[API-App] Preview deployment created: id={}", deployment.id);
+        let preview_url = format!("/preview/{}/", deployment.id);
+        return Ok(DeployOutcome::Preview {
+            app,
+            deployment,
+            preview_url,
         });
     }
 
-    (
-        StatusCode::OK,
-        Json(DataJsonResponse {
-            data: Some(app),
-            error: None,
-        }),
-    )
+    // Publish to IPNS and pin on nodes through the job queue
+    if let Err(e) = start_publication(state, &app, &deployment).await {
+        eprintln!("[API-App] {}", e);
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, e));
+    }
+
+    Ok(DeployOutcome::Live(app))
 }
 
 async fn find_or_create_app(db_pool: &DbPool, payload: &AppDeployPayload) -> Result<App, String> {
@@ -393,102 +587,3 @@ async fn find_or_create_app(db_pool: &DbPool, payload: &AppDeployPayload) -> Res
         }
     }
 }
-
-async fn find_or_create_ipns_key(ipfs_host: &str, app_name: &str) -> Result<KeyInfo, String> {
-    let client = Client::new();
-    let key_url = format!("{}/api/v0/key/list", ipfs_host);
-
-    let resp = client
-        .post(key_url)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    if !resp.status().is_success() {
-        return Err(format!("[IPNS] Key list error: {}", resp.status()));
-    }
-    let key_list: KeyListResponse = resp.json().await.map_err(|e| e.to_string())?;
-
-    if let Some(key) = key_list.keys.into_iter().find(|k| k.name == app_name) {
-        println!("[IPNS] Key found for \"{}\"", app_name);
-        return Ok(key);
-    }
-
-    println!("[IPNS] Key not found, creation for \"{}\"", app_name);
-    let create_url = format!("{}/api/v0/key/gen?arg={}&type=ed25519", ipfs_host, app_name);
-    let create_resp = client
-        .post(create_url)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    if !create_resp.status().is_success() {
-        return Err(format!("[IPNS] Key gen error: {}", create_resp.status()));
-    }
-
-    let key_info: KeyInfo = create_resp.json().await.map_err(|e| e.to_string())?;
-    Ok(key_info)
-}
-
-async fn publish_to_ipns(
-    ipfs_host: &str,
-    key_name: &str,
-    cid: &str,
-) -> Result<IpnsPublishResponse, String> {
-    let client = Client::new();
-    let ipfs_path = format!("/ipfs/{}", cid);
-    let publish_url = format!(
-        "{}/api/v0/name/publish?key={}&arg={}",
-        ipfs_host, key_name, ipfs_path
-    );
-
-    let resp = client
-        .post(publish_url)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if !resp.status().is_success() {
-        let error_text = resp.text().await.unwrap_or_default();
-        return Err(format!("[IPNS] Publishing failed: {}", error_text));
-    }
-
-    let publish_info: IpnsPublishResponse = resp.json().await.map_err(|e| e.to_string())?;
-    Ok(publish_info)
-}
-
-async fn add_to_ipfs(ipfs_host: &str, file_path: &str) -> Result<String, String> {
-    let file = fs::read(file_path).await.map_err(|e| e.to_string())?;
-    let part = multipart::Part::bytes(file).file_name("deploy.tmp");
-    let form = multipart::Form::new().part("file", part);
-
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(format!("{}/api/v0/add", ipfs_host))
-        .multipart(form)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if !resp.status().is_success() {
-        return Err(format!("[API-App] IPFS error {}", resp.status()));
-    }
-
-    let kubo_resp: IPFSAddResponse = resp.json().await.map_err(|e| e.to_string())?;
-
-    Ok(kubo_resp.hash)
-}
-
-async fn select_nodes_deployable(state: &ServerState) -> Result<HashMap<String, Node>, String> {
-    match sqlx::query_as::<_, Node>("SELECT * FROM nodes WHERE reputation_score > 0.8 LIMIT 3")
-        .fetch_all(&state.db_pool)
-        .await
-    {
-        Ok(nodes) => {
-            let mut nodes_map = HashMap::new();
-            for node in nodes {
-                nodes_map.insert(node.id.to_string(), node);
-            }
-            Ok(nodes_map)
-        }
-        Err(e) => Err(format!("Error selecting nodes from database: {}", e)),
-    }
-}
@@ -0,0 +1,137 @@
+use std::convert::Infallible;
+
+use async_stream::stream;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures_util::Stream;
+use sqlx::types::Uuid;
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+
+use kc_core::{
+    authentication,
+    events::{DeploymentEvent, deployment_snapshot, settled_event},
+    json::DataJsonResponse,
+    models::{app::App, deployment::Deployment},
+    server::ServerState,
+};
+
+pub async fn events(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    authenticated_claims: authentication::Claims,
+) -> Response {
+    let deployment = match Deployment::find_by_id(&state.db_pool, &id).await {
+        Ok(deployment) => deployment,
+        Err(e) => {
+            println!("[API-App] Deployment not found: {}", e);
+            return error_response(StatusCode::NOT_FOUND, "Deployment not found");
+        }
+    };
+
+    let app = match App::find_by_id(&state.db_pool, &deployment.app_id.to_string()).await {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("[API-App] App of deployment {} not found: {}", id, e);
+            return error_response(StatusCode::NOT_FOUND, "App not found");
+        }
+    };
+    match app
+        .is_managed_by(&state.db_pool, &authenticated_claims)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return error_response(
+                StatusCode::FORBIDDEN,
+                "You do not have permission to perform this action.",
+            );
+        }
+        Err(e) => {
+            eprintln!("[API-App] Permission check failed: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Permission check failed");
+        }
+    }
+
+    // Subscribe before reading the snapshot so no transition falls in between
+    let receiver = state.event_bus.subscribe();
+    Sse::new(deployment_events(state, deployment.id, receiver))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(DataJsonResponse::<()> {
+            error: Some(message.to_string()),
+            data: None,
+        }),
+    )
+        .into_response()
+}
+
+fn deployment_events(
+    state: ServerState,
+    deployment_id: Uuid,
+    mut receiver: Receiver<DeploymentEvent>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream! {
+        let id = deployment_id.to_string();
+        let mut needs_snapshot = true;
+
+        loop {
+            // Sent on connection, and again if the receiver fell behind the bus
+            if needs_snapshot {
+                needs_snapshot = false;
+                let events = match deployment_snapshot(&state.db_pool, &deployment_id).await {
+                    Ok(events) => events,
+                    Err(e) => {
+                        eprintln!("[API-App] Deployment {} snapshot error: {}", id, e);
+                        break;
+                    }
+                };
+
+                let mut settled = false;
+                for event in events {
+                    settled = matches!(event, DeploymentEvent::Settled { .. });
+                    yield Ok(to_sse_event(&event));
+                }
+                if settled {
+                    break;
+                }
+            }
+
+            match receiver.recv().await {
+                Ok(event) if event.deployment_id() == id => {
+                    yield Ok(to_sse_event(&event));
+                    match settled_event(&state.db_pool, &deployment_id).await {
+                        Ok(Some(settled)) => {
+                            yield Ok(to_sse_event(&settled));
+                            break;
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            eprintln!("[API-App] Deployment {} status error: {}", id, e);
+                            break;
+                        }
+                    }
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => needs_snapshot = true,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+fn to_sse_event(event: &DeploymentEvent) -> Event {
+    let data = serde_json::to_string(event).unwrap_or_default();
+    Event::default().event(event.name()).data(data)
+}
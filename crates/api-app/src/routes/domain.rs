@@ -0,0 +1,288 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+
+use kc_core::{
+    app::forget_host,
+    authentication,
+    domain::{validate_hostname, verify_domain},
+    json::DataJsonResponse,
+    models::{app::App, domain::Domain},
+    payloads::domain::CreateDomainPayload,
+    server::ServerState,
+};
+use reqwest::StatusCode;
+
+use crate::routes::app::find_managed_app;
+
+#[derive(Deserialize, Debug)]
+pub struct AddDomainPayload {
+    hostname: String,
+}
+
+type DomainResponse = (StatusCode, Json<DataJsonResponse<Domain>>);
+
+pub async fn list(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    authenticated_claims: authentication::Claims,
+) -> impl IntoResponse {
+    let app = match find_managed_app(&state, &id, &authenticated_claims).await {
+        Ok(app) => app,
+        Err(response) => return response,
+    };
+
+    match Domain::find_by_app_id(&state.db_pool, &app.id).await {
+        Ok(domains) => (
+            StatusCode::OK,
+            Json(DataJsonResponse {
+                data: Some(domains),
+                error: None,
+            }),
+        ),
+        Err(e) => {
+            eprintln!("[API-App] Error in retrieving domains: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DataJsonResponse {
+                    error: Some("Error in retrieving domains".to_string()),
+                    data: None,
+                }),
+            )
+        }
+    }
+}
+
+pub async fn add(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    authenticated_claims: authentication::Claims,
+    Json(payload): Json<AddDomainPayload>,
+) -> impl IntoResponse {
+    let app = match find_managed_app(&state, &id, &authenticated_claims).await {
+        Ok(app) => app,
+        Err(response) => return response,
+    };
+
+    let hostname = match validate_hostname(&payload.hostname) {
+        Ok(hostname) => hostname,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(DataJsonResponse {
+                    error: Some(e),
+                    data: None,
+                }),
+            );
+        }
+    };
+
+    // Claims awaiting verification do not keep the owner of the hostname from adding it
+    match Domain::find_verified_by_hostname(&state.db_pool, &hostname).await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(DataJsonResponse {
+                    error: Some("Domain is already registered".to_string()),
+                    data: None,
+                }),
+            );
+        }
+        Err(e) => {
+            eprintln!("[API-App] Error in retrieving domains: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DataJsonResponse {
+                    error: Some("Error in retrieving domains".to_string()),
+                    data: None,
+                }),
+            );
+        }
+    }
+
+    match Domain::create(
+        &state.db_pool,
+        &CreateDomainPayload {
+            app_id: app.id.to_string(),
+            hostname,
+        },
+    )
+    .await
+    {
+        Ok(domain) => {
+            println!(
+                "[API-App] Domain {} added to app \"{}\", awaiting verification",
+                domain.hostname, app.name
+            );
+            (
+                StatusCode::CREATED,
+                Json(DataJsonResponse {
+                    data: Some(domain),
+                    error: None,
+                }),
+            )
+        }
+        Err(e) => {
+            eprintln!("[API-App] Domain creation failed: {}", e);
+            (
+                StatusCode::CONFLICT,
+                Json(DataJsonResponse {
+                    error: Some("Domain is already registered".to_string()),
+                    data: None,
+                }),
+            )
+        }
+    }
+}
+
+pub async fn verify(
+    State(state): State<ServerState>,
+    Path((id, domain_id)): Path<(String, String)>,
+    authenticated_claims: authentication::Claims,
+) -> impl IntoResponse {
+    let (app, domain) =
+        match find_managed_domain(&state, &id, &domain_id, &authenticated_claims).await {
+            Ok(found) => found,
+            Err(response) => return response,
+        };
+    if domain.verified_at.is_some() {
+        return (
+            StatusCode::OK,
+            Json(DataJsonResponse {
+                data: Some(domain),
+                error: None,
+            }),
+        );
+    }
+
+    match Domain::find_verified_by_hostname(&state.db_pool, &domain.hostname).await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(DataJsonResponse {
+                    error: Some("Domain is already verified for another app".to_string()),
+                    data: None,
+                }),
+            );
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DataJsonResponse {
+                    error: Some(e),
+                    data: None,
+                }),
+            );
+        }
+    }
+
+    if let Err(e) = verify_domain(&state.server_settings.domains, &app, &domain).await {
+        println!(
+            "[API-App] Verification of domain {} failed: {}",
+            domain.hostname, e
+        );
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        );
+    }
+
+    match domain.mark_verified(&state.db_pool).await {
+        Ok(domain) => {
+            forget_host(&state.domain_registry, &domain.hostname);
+            println!(
+                "[API-App] Domain {} verified for app \"{}\"",
+                domain.hostname, app.name
+            );
+            (
+                StatusCode::OK,
+                Json(DataJsonResponse {
+                    data: Some(domain),
+                    error: None,
+                }),
+            )
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        ),
+    }
+}
+
+pub async fn remove(
+    State(state): State<ServerState>,
+    Path((id, domain_id)): Path<(String, String)>,
+    authenticated_claims: authentication::Claims,
+) -> impl IntoResponse {
+    let (app, domain) =
+        match find_managed_domain(&state, &id, &domain_id, &authenticated_claims).await {
+            Ok(found) => found,
+            Err(response) => return response,
+        };
+
+    match Domain::delete_by_id(&state.db_pool, &domain.id.to_string()).await {
+        Ok(domain) => {
+            forget_host(&state.domain_registry, &domain.hostname);
+            println!(
+                "[API-App] Domain {} removed from app \"{}\"",
+                domain.hostname, app.name
+            );
+            (
+                StatusCode::OK,
+                Json(DataJsonResponse {
+                    data: Some(domain),
+                    error: None,
+                }),
+            )
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        ),
+    }
+}
+
+/// Loads an app and one of its domains, if the user is allowed to manage the app.
+async fn find_managed_domain(
+    state: &ServerState,
+    id: &String,
+    domain_id: &str,
+    claims: &authentication::Claims,
+) -> Result<(App, Domain), DomainResponse> {
+    let app = find_managed_app(state, id, claims).await?;
+
+    match Domain::find_by_id(&state.db_pool, domain_id).await {
+        Ok(domain) if domain.app_id == app.id => Ok((app, domain)),
+        Ok(_) => Err((
+            StatusCode::NOT_FOUND,
+            Json(DataJsonResponse {
+                error: Some("Domain not found".to_string()),
+                data: None,
+            }),
+        )),
+        Err(e) => {
+            println!("[API-App] Domain not found: {}", e);
+            Err((
+                StatusCode::NOT_FOUND,
+                Json(DataJsonResponse {
+                    error: Some("Domain not found".to_string()),
+                    data: None,
+                }),
+            ))
+        }
+    }
+}
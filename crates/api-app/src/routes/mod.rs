@@ -1,2 +1,5 @@
+pub mod alias;
 pub mod app;
 pub mod deploy;
+pub mod deployment;
+pub mod domain;
@@ -10,5 +10,15 @@ pub fn create_router() -> Router<ServerState> {
         .route("/{uuid}", get(routes::node::get))
         .route("/mine", get(routes::node::get_mine))
         .route("/", post(routes::node::post))
+        .route(
+            "/{uuid}/credentials",
+            post(routes::node::rotate_credentials).delete(routes::node::revoke_credentials),
+        )
+        .route("/{uuid}/reputation", get(routes::reputation::get))
+        .route(
+            "/{uuid}/reputation/history",
+            get(routes::reputation::get_history),
+        )
         .route("/heartbeat", post(routes::heartbeat::post))
+        .route("/pins/{deployment_node_id}", post(routes::pin::post))
 }
@@ -5,38 +5,57 @@ use serde::Deserialize;
 
 use kc_core::{
     json::SimpleJsonResponse,
-    models::node::{Node, NodeInfo},
+    models::node::{NodeInfo, NodeTelemetry},
+    node::{SignedNode, validate_telemetry},
     server::ServerState,
 };
 
 #[derive(Deserialize, Debug)]
 pub struct HeartbeatPayload {
     id: String,
+    telemetry: Option<NodeTelemetry>,
 }
 
-pub async fn post(
-    State(state): State<ServerState>,
-    Json(payload): Json<HeartbeatPayload>,
-) -> impl IntoResponse {
-    let mut conn = match state.redis_client.get_multiplexed_tokio_connection().await {
-        Ok(conn) => conn,
-        Err(_) => {
+pub async fn post(State(state): State<ServerState>, signed_node: SignedNode) -> impl IntoResponse {
+    let payload = match signed_node.json::<HeartbeatPayload>() {
+        Ok(payload) => payload,
+        Err(e) => {
             return (
-                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_REQUEST,
                 Json(SimpleJsonResponse {
-                    message: "Error Redis connection".to_string(),
+                    message: format!("Invalid heartbeat: {}", e),
                 }),
             );
         }
     };
+    // A node only vouches for itself
+    if payload.id != signed_node.node.id.to_string() {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(SimpleJsonResponse {
+                message: "Heartbeat id does not match the signing node".to_string(),
+            }),
+        );
+    }
+    if let Some(telemetry) = &payload.telemetry
+        && let Err(e) = validate_telemetry(telemetry)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(SimpleJsonResponse {
+                message: format!("Invalid telemetry: {}", e),
+            }),
+        );
+    }
+    let node = signed_node.node;
 
-    let node = match Node::find_by_id(&state.db_pool, &payload.id).await {
-        Ok(node) => node,
+    let mut conn = match state.redis_client.get_multiplexed_tokio_connection().await {
+        Ok(conn) => conn,
         Err(_) => {
             return (
-                StatusCode::NOT_FOUND,
+                StatusCode::INTERNAL_SERVER_ERROR,
                 Json(SimpleJsonResponse {
-                    message: format!("Node id={} not found", payload.id),
+                    message: "Error Redis connection".to_string(),
                 }),
             );
         }
@@ -54,6 +73,7 @@ pub async fn post(
     let info_updated_json = match serde_json::from_str::<NodeInfo>(&info_json) {
         Ok(mut info) => {
             info.last_seen = Some(Utc::now().timestamp());
+            info.telemetry = payload.telemetry;
             serde_json::to_string(&info).unwrap_or(info_json)
         }
         Err(e) => {
@@ -1,2 +1,4 @@
 pub mod heartbeat;
 pub mod node;
+pub mod pin;
+pub mod reputation;
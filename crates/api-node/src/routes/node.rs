@@ -6,25 +6,79 @@ use axum::{
 };
 use chrono::Utc;
 use redis::AsyncTypedCommands;
+use serde::Serialize;
+use sqlx::types::Uuid;
 use std::net::SocketAddr;
 
 use kc_core::{
     authentication,
     json::DataJsonResponse,
-    models::node::{Node, NodeData, NodeInfo},
+    models::{
+        node::{Node, NodeCredentials, NodeData, NodeInfo},
+        team::Team,
+    },
     payloads::node::CreateNodePayload,
+    selection::validate_region,
     server::ServerState,
 };
 
+/// Registers a node for a team of the user, returning its secret, which is not shown again.
 pub async fn post(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<ServerState>,
+    authenticated_claims: authentication::Claims,
     Json(mut payload): Json<CreateNodePayload>,
 ) -> impl IntoResponse {
     println!("[API-Nodes] Registration received.");
 
+    let owner_id = match Uuid::parse_str(&payload.owner_id) {
+        Ok(owner_id) => owner_id,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(DataJsonResponse {
+                    error: Some(format!("Invalid owner uuid format: {}", e)),
+                    data: None,
+                }),
+            );
+        }
+    };
+    if let Some(region) = &payload.region
+        && let Err(e) = validate_region(region)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        );
+    }
+    match Team::is_managed_by(&state.db_pool, &owner_id, &authenticated_claims).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(DataJsonResponse {
+                    error: Some("Nodes can only be registered for your teams".to_string()),
+                    data: None,
+                }),
+            );
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DataJsonResponse {
+                    error: Some(e),
+                    data: None,
+                }),
+            );
+        }
+    }
+
     let info = NodeInfo {
         last_seen: Some(Utc::now().timestamp()),
+        telemetry: None,
     };
     payload.ip = Some(addr.ip().to_string());
 
@@ -68,10 +122,11 @@ pub async fn post(
             {
                 Ok(_) => {
                     println!("[API-Nodes] Node registered in Redis");
+                    let secret = node.secret.clone().unwrap_or_default();
                     return (
                         StatusCode::OK,
                         Json(DataJsonResponse {
-                            data: Some(node),
+                            data: Some(NodeCredentials { node, secret }),
                             error: None,
                         }),
                     );
@@ -206,3 +261,107 @@ pub async fn get(State(state): State<ServerState>, Path(uuid): Path<String>) ->
         }),
     )
 }
+
+/// Issues a new secret to the node, the previous one no longer signing its requests.
+pub async fn rotate_credentials(
+    State(state): State<ServerState>,
+    Path(uuid): Path<String>,
+    authenticated_claims: authentication::Claims,
+) -> impl IntoResponse {
+    let node = match find_managed_node(&state, &uuid, &authenticated_claims).await {
+        Ok(node) => node,
+        Err(response) => return response,
+    };
+
+    match node.rotate_secret(&state.db_pool).await {
+        Ok(node) => {
+            println!("[API-Nodes] Credentials rotated: id={}", node.id);
+            let secret = node.secret.clone().unwrap_or_default();
+            (
+                StatusCode::OK,
+                Json(DataJsonResponse {
+                    data: Some(NodeCredentials { node, secret }),
+                    error: None,
+                }),
+            )
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        ),
+    }
+}
+
+/// Revokes the secret of the node, whose heartbeats and callbacks are rejected until rotation.
+pub async fn revoke_credentials(
+    State(state): State<ServerState>,
+    Path(uuid): Path<String>,
+    authenticated_claims: authentication::Claims,
+) -> impl IntoResponse {
+    let node = match find_managed_node(&state, &uuid, &authenticated_claims).await {
+        Ok(node) => node,
+        Err(response) => return response,
+    };
+
+    match node.revoke_secret(&state.db_pool).await {
+        Ok(node) => {
+            println!("[API-Nodes] Credentials revoked: id={}", node.id);
+            (
+                StatusCode::OK,
+                Json(DataJsonResponse {
+                    data: Some(node),
+                    error: None,
+                }),
+            )
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        ),
+    }
+}
+
+/// Loads a node, if the user is a member of the team owning it.
+async fn find_managed_node<T: Serialize>(
+    state: &ServerState,
+    uuid: &String,
+    claims: &authentication::Claims,
+) -> Result<Node, (StatusCode, Json<DataJsonResponse<T>>)> {
+    let node = match Node::find_by_id(&state.db_pool, uuid).await {
+        Ok(node) => node,
+        Err(e) => {
+            println!("[API-Nodes] Node not found: {}", e);
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(DataJsonResponse {
+                    error: Some("Node not found".to_string()),
+                    data: None,
+                }),
+            ));
+        }
+    };
+
+    match Team::is_managed_by(&state.db_pool, &node.owner_id, claims).await {
+        Ok(true) => Ok(node),
+        Ok(false) => Err((
+            StatusCode::FORBIDDEN,
+            Json(DataJsonResponse {
+                error: Some("Insufficient permissions to manage this node".to_string()),
+                data: None,
+            }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(DataJsonResponse {
+                error: Some(e),
+                data: None,
+            }),
+        )),
+    }
+}
@@ -0,0 +1,149 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use kc_core::{
+    json::SimpleJsonResponse,
+    models::deployment_node::{DeploymentNode, PinStatus},
+    node::SignedNode,
+    payloads::node::PinConfirmationPayload,
+    server::ServerState,
+    utils::auth::secrets_match,
+};
+
+pub async fn post(
+    State(state): State<ServerState>,
+    Path(deployment_node_id): Path<String>,
+    signed_node: SignedNode,
+) -> impl IntoResponse {
+    let payload = match signed_node.json::<PinConfirmationPayload>() {
+        Ok(payload) => payload,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(SimpleJsonResponse {
+                    message: format!("Invalid pin confirmation: {}", e),
+                }),
+            );
+        }
+    };
+
+    let deployment_node =
+        match DeploymentNode::find_by_id(&state.db_pool, &deployment_node_id).await {
+            Ok(deployment_node) => deployment_node,
+            Err(_) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(SimpleJsonResponse {
+                        message: format!("Pin id={} not found", deployment_node_id),
+                    }),
+                );
+            }
+        };
+
+    // Only the node asked to pin the content can confirm it
+    if deployment_node.node_id != signed_node.node.id {
+        println!(
+            "[API-Nodes] Rejected pin confirmation from another node: id={}, node={}",
+            deployment_node_id, signed_node.node.id
+        );
+        return (
+            StatusCode::FORBIDDEN,
+            Json(SimpleJsonResponse {
+                message: format!("Pin {} belongs to another node", deployment_node_id),
+            }),
+        );
+    }
+
+    let authorized = match &deployment_node.callback_token {
+        Some(token) => secrets_match(token, &payload.token),
+        None => false,
+    };
+    if !authorized {
+        println!(
+            "[API-Nodes] Rejected pin confirmation with invalid token: id={}",
+            deployment_node_id
+        );
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(SimpleJsonResponse {
+                message: "Invalid pin callback token".to_string(),
+            }),
+        );
+    }
+
+    if matches!(
+        payload.status,
+        PinStatus::PINNING | PinStatus::LOST | PinStatus::UNPINNED
+    ) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(SimpleJsonResponse {
+                message: "Pin status must be PINNED or FAILED".to_string(),
+            }),
+        );
+    }
+
+    // The content was released by the retention policy, a late pin must not revive it
+    if matches!(deployment_node.status, PinStatus::UNPINNED) {
+        return (
+            StatusCode::CONFLICT,
+            Json(SimpleJsonResponse {
+                message: format!("Pin {} has been released", deployment_node_id),
+            }),
+        );
+    }
+
+    // A confirmed pin is final, repeated callbacks are acknowledged without effect
+    if matches!(deployment_node.status, PinStatus::PINNED) {
+        return (
+            StatusCode::OK,
+            Json(SimpleJsonResponse {
+                message: format!("Pin {} already confirmed", deployment_node_id),
+            }),
+        );
+    }
+
+    match deployment_node
+        .confirm(&state.db_pool, payload.status)
+        .await
+    {
+        Ok(Some(deployment_node)) => {
+            println!(
+                "[API-Nodes] Pin confirmation received: id={}, node={}, status={:?}",
+                deployment_node.id, deployment_node.node_id, deployment_node.status
+            );
+            (
+                StatusCode::OK,
+                Json(SimpleJsonResponse {
+                    message: format!("Pin {} updated", deployment_node.id),
+                }),
+            )
+        }
+        // Timed out or lost in the meantime, the replica was already given up on
+        Ok(None) => {
+            println!(
+                "[API-Nodes] Rejected late pin confirmation: id={}",
+                deployment_node_id
+            );
+            (
+                StatusCode::CONFLICT,
+                Json(SimpleJsonResponse {
+                    message: format!("Pin {} is no longer pending", deployment_node_id),
+                }),
+            )
+        }
+        Err(e) => {
+            eprintln!("[API-Nodes] Pin confirmation error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(SimpleJsonResponse {
+                    message: "Error updating pin status".to_string(),
+                }),
+            )
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use kc_core::{
+    json::DataJsonResponse,
+    models::{node::Node, node_reputation::NodeReputation},
+    server::ServerState,
+};
+
+/// Latest score of the node, with the value and weight of each criterion behind it.
+pub async fn get(State(state): State<ServerState>, Path(uuid): Path<String>) -> impl IntoResponse {
+    let node = match Node::find_by_id(&state.db_pool, &uuid).await {
+        Ok(node) => node,
+        Err(e) => {
+            println!("[API-Nodes] Node not found: {}", e);
+            return (
+                StatusCode::NOT_FOUND,
+                Json(DataJsonResponse {
+                    error: Some("Node not found".to_string()),
+                    data: None,
+                }),
+            );
+        }
+    };
+
+    match NodeReputation::find_latest_by_node_id(&state.db_pool, &node.id).await {
+        Ok(Some(reputation)) => (
+            StatusCode::OK,
+            Json(DataJsonResponse {
+                data: Some(reputation),
+                error: None,
+            }),
+        ),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(DataJsonResponse {
+                error: Some(format!(
+                    "Node {} has not been scored yet, its reputation is {}",
+                    node.id, node.reputation_score
+                )),
+                data: None,
+            }),
+        ),
+        Err(e) => {
+            println!("[API-Nodes] Error fetching reputation from DB: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DataJsonResponse {
+                    error: Some("Error fetching reputation from DB".to_string()),
+                    data: None,
+                }),
+            )
+        }
+    }
+}
+
+/// Latest scores of the node, newest first.
+pub async fn get_history(
+    State(state): State<ServerState>,
+    Path(uuid): Path<String>,
+) -> impl IntoResponse {
+    let node = match Node::find_by_id(&state.db_pool, &uuid).await {
+        Ok(node) => node,
+        Err(e) => {
+            println!("[API-Nodes] Node not found: {}", e);
+            return (
+                StatusCode::NOT_FOUND,
+                Json(DataJsonResponse {
+                    error: Some("Node not found".to_string()),
+                    data: None,
+                }),
+            );
+        }
+    };
+
+    match NodeReputation::find_by_node_id(&state.db_pool, &node.id).await {
+        Ok(history) => (
+            StatusCode::OK,
+            Json(DataJsonResponse {
+                data: Some(history),
+                error: None,
+            }),
+        ),
+        Err(e) => {
+            println!("[API-Nodes] Error fetching reputation from DB: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DataJsonResponse {
+                    error: Some("Error fetching reputation from DB".to_string()),
+                    data: None,
+                }),
+            )
+        }
+    }
+}
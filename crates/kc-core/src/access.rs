@@ -0,0 +1,135 @@
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use crate::models::app::{AccessMode, App};
+
+// Passwords are typed by visitors, not generated, so a floor keeps out the trivial ones
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Lifetime of the session tokens signing team members in to TEAM apps from a URL, which only
+/// have to last until the gateway swaps them for a cookie
+pub const TEAM_SIGN_IN_TTL_SECONDS: u64 = 60;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AccessConfig {
+    /// How long a visitor stays signed in to a private app after a successful authentication
+    pub session_ttl_seconds: u64,
+    /// Longest validity of a signed link, also used when none is asked for
+    pub max_link_ttl_seconds: u64,
+    /// Only sends session cookies over HTTPS, to disable when the gateway is served over HTTP
+    pub secure_cookies: bool,
+}
+
+/// What the gateway needs to know to let a visitor in an app, cached with the app CID.
+#[derive(Debug, Clone)]
+pub struct AppAccess {
+    pub app_id: Uuid,
+    pub team_id: Uuid,
+    pub mode: AccessMode,
+    pub password_hash: Option<String>,
+    pub version: i32,
+}
+
+impl From<&App> for AppAccess {
+    fn from(app: &App) -> Self {
+        AppAccess {
+            app_id: app.id,
+            team_id: app.team_id,
+            mode: app.access_mode,
+            password_hash: app.access_password_hash.clone(),
+            version: app.access_version,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessScope {
+    /// Signed link shared by the team, valid in TOKEN mode
+    Link,
+    /// Cookie set after a successful authentication, valid in the mode it was issued for
+    Session,
+}
+
+/// Claims of access tokens, signed with the JWT secret but never accepted as user tokens.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccessClaims {
+    pub app_id: String,
+    pub scope: AccessScope,
+    pub mode: AccessMode,
+    pub version: i32,
+    pub exp: usize,
+}
+
+pub fn validate_access(mode: AccessMode, password: Option<&str>) -> Result<(), String> {
+    match (mode, password) {
+        (AccessMode::PASSWORD, None) => Err("PASSWORD mode requires a password".to_string()),
+        (AccessMode::PASSWORD, Some(password))
+            if password.chars().count() < MIN_PASSWORD_LENGTH =>
+        {
+            Err(format!(
+                "Passwords must be at least {} characters long",
+                MIN_PASSWORD_LENGTH
+            ))
+        }
+        (AccessMode::PASSWORD, Some(_)) | (_, None) => Ok(()),
+        (_, Some(_)) => Err(format!(
+            "Passwords are only used in PASSWORD mode, not {}",
+            mode.name()
+        )),
+    }
+}
+
+/// Signs an access token for an app, returning it with its expiry date.
+pub fn issue_access_token(
+    jwt_secret: &str,
+    access: &AppAccess,
+    scope: AccessScope,
+    ttl_seconds: u64,
+) -> Result<(String, DateTime<Utc>), String> {
+    let expires_at = Utc::now() + Duration::seconds(ttl_seconds as i64);
+    let claims = AccessClaims {
+        app_id: access.app_id.to_string(),
+        scope,
+        mode: access.mode,
+        version: access.version,
+        exp: expires_at.timestamp() as usize,
+    };
+
+    match encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt_secret.as_ref()),
+    ) {
+        Ok(token) => Ok((token, expires_at)),
+        Err(e) => Err(format!("Failed to generate access token: {}", e)),
+    }
+}
+
+/// Checks that a token was issued for the app, with the scope and for its current access.
+///
+/// Changing the access of an app, its password included, thus signs out every visitor.
+pub fn verify_access_token(
+    jwt_secret: &str,
+    access: &AppAccess,
+    scope: AccessScope,
+    token: &str,
+) -> bool {
+    let decoding_key = DecodingKey::from_secret(jwt_secret.as_ref());
+    match decode::<AccessClaims>(token, &decoding_key, &Validation::default()) {
+        Ok(token_data) => {
+            token_data.claims.app_id == access.app_id.to_string()
+                && token_data.claims.scope == scope
+                && token_data.claims.mode == access.mode
+                && token_data.claims.version == access.version
+        }
+        Err(_) => false,
+    }
+}
+
+/// Each app gets its own cookie, as apps served under `/app/` share the gateway origin.
+pub fn session_cookie_name(app_id: &Uuid) -> String {
+    format!("keyston_access_{}", app_id.simple())
+}
@@ -1,12 +1,165 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{
+    access::AppAccess,
+    ipfs::IpfsError,
+    models::{app::App, deployment::Deployment, domain::Domain},
+    server::ServerState,
+    site_rules::SiteRules,
+};
 
 #[derive(Debug, Clone)]
 pub struct AppInfo {
     pub name: String,
     pub current_cid: String,
-    pub key_name: String,
-    pub ipns_name: String,
+    pub key_name: Option<String>,
+    pub ipns_name: Option<String>,
+    pub site_rules: Option<SiteRules>,
+    pub access: AppAccess,
+    pub resolved_at: Instant,
 }
 
 pub type AppRegistry = Arc<Mutex<HashMap<String, AppInfo>>>;
+
+/// App served on each hostname seen by the gateway, `None` for hostnames that are not custom domains.
+pub type DomainRegistry = Arc<Mutex<HashMap<String, (Option<String>, Instant)>>>;
+
+// Any Host header ends up in the domain registry, so it is emptied past this size
+const DOMAIN_REGISTRY_LIMIT: usize = 10_000;
+
+#[derive(Debug)]
+pub enum AppResolveError {
+    NotFound(String),
+    Unavailable(String),
+}
+
+/// Drops the cached CID of an app, the next request reads it again from the database.
+pub fn forget_app(registry: &AppRegistry, app_name: &str) {
+    registry.lock().unwrap().remove(app_name);
+}
+
+/// Drops the cached app of a hostname, after its domain was verified or removed.
+pub fn forget_host(registry: &DomainRegistry, hostname: &str) {
+    registry.lock().unwrap().remove(hostname);
+}
+
+/// Finds the app served on a verified custom domain.
+pub async fn resolve_host(state: &ServerState, hostname: &str) -> Result<Option<String>, String> {
+    let ttl = Duration::from_secs(state.server_settings.gateway.app_cache_ttl_seconds);
+    if let Some((app_name, resolved_at)) = state.domain_registry.lock().unwrap().get(hostname)
+        && resolved_at.elapsed() < ttl
+    {
+        return Ok(app_name.clone());
+    }
+
+    let app_name = Domain::find_app_name_by_hostname(&state.db_pool, hostname).await?;
+
+    let mut registry = state.domain_registry.lock().unwrap();
+    if registry.len() >= DOMAIN_REGISTRY_LIMIT {
+        registry.clear();
+    }
+    registry.insert(hostname.to_string(), (app_name.clone(), Instant::now()));
+
+    Ok(app_name)
+}
+
+/// Finds the CID currently served for an app.
+///
+/// The live deployment recorded in the database is the source of truth, IPNS is only asked
+/// when the app has none. While the database or IPFS is unreachable, the last CID resolved
+/// keeps being served, so an outage does not take the sites down.
+pub async fn resolve_app(state: &ServerState, app_name: &str) -> Result<AppInfo, AppResolveError> {
+    let ttl = Duration::from_secs(state.server_settings.gateway.app_cache_ttl_seconds);
+    let cached = state.app_registry.lock().unwrap().get(app_name).cloned();
+    if let Some(info) = &cached
+        && info.resolved_at.elapsed() < ttl
+    {
+        return Ok(info.clone());
+    }
+
+    match find_app_info(state, app_name).await {
+        Ok(info) => {
+            state
+                .app_registry
+                .lock()
+                .unwrap()
+                .insert(info.name.clone(), info.clone());
+            Ok(info)
+        }
+        Err(AppResolveError::Unavailable(e)) => match cached {
+            Some(info) => {
+                eprintln!(
+                    "[Registry] Serving app \"{}\" from its expired CID {}: {}",
+                    app_name, info.current_cid, e
+                );
+                Ok(info)
+            }
+            None => Err(AppResolveError::Unavailable(e)),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+async fn find_app_info(state: &ServerState, app_name: &str) -> Result<AppInfo, AppResolveError> {
+    let app = App::find_optional_by_name(&state.db_pool, app_name)
+        .await
+        .map_err(AppResolveError::Unavailable)?
+        .ok_or_else(|| AppResolveError::NotFound(format!("App {} not found", app_name)))?;
+    let live = Deployment::find_live_by_app_id(&state.db_pool, &app.id)
+        .await
+        .map_err(AppResolveError::Unavailable)?;
+
+    // Site rules are only known for deployments recorded in the database
+    let (current_cid, site_rules) = match live {
+        Some(deployment) => {
+            if let Some(ipns_name) = &app.ipns_name {
+                verify_ipns(state, &app.name, ipns_name, &deployment.cid);
+            }
+            (deployment.cid, deployment.site_rules.map(|rules| rules.0))
+        }
+        None => {
+            let ipns_name = app.ipns_name.as_deref().ok_or_else(|| {
+                AppResolveError::NotFound(format!("App {} has no deployment", app.name))
+            })?;
+            match state.ipfs.name_resolve(ipns_name).await {
+                Ok(cid) => (cid, None),
+                Err(IpfsError::NotFound(e)) => return Err(AppResolveError::NotFound(e)),
+                Err(e) => return Err(AppResolveError::Unavailable(e.to_string())),
+            }
+        }
+    };
+
+    Ok(AppInfo {
+        access: AppAccess::from(&app),
+        name: app.name,
+        current_cid,
+        key_name: app.key_name,
+        ipns_name: app.ipns_name,
+        site_rules,
+        resolved_at: Instant::now(),
+    })
+}
+
+/// Checks in the background that IPNS still points to the live deployment.
+fn verify_ipns(state: &ServerState, app_name: &str, ipns_name: &str, cid: &str) {
+    let ipfs = state.ipfs.clone();
+    let app_name = app_name.to_string();
+    let ipns_name = ipns_name.to_string();
+    let cid = cid.to_string();
+
+    tokio::spawn(async move {
+        match ipfs.name_resolve(&ipns_name).await {
+            Ok(resolved) if resolved != cid => eprintln!(
+                "[Registry] IPNS name of app \"{}\" points to {} instead of live CID {}",
+                app_name, resolved, cid
+            ),
+            Ok(_) => {}
+            Err(e) => eprintln!(
+                "[Registry] Error in IPNS verification of app \"{}\": {}",
+                app_name, e
+            ),
+        }
+    });
+}
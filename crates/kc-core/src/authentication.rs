@@ -55,12 +55,17 @@ impl FromRequestParts<ServerState> for Claims {
             TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
                 .await
                 .map_err(|_| AuthError::MissingToken)?;
-        let decoding_key =
-            DecodingKey::from_secret(state.server_settings.server.jwt_secret.as_ref());
 
-        let token_data = decode::<Claims>(bearer.token(), &decoding_key, &Validation::default())
-            .map_err(|_| AuthError::InvalidToken)?;
-
-        Ok(token_data.claims)
+        decode_claims(&state.server_settings.server.jwt_secret, bearer.token())
+            .ok_or(AuthError::InvalidToken)
     }
 }
+
+/// Decodes a user token, `None` when it is invalid or expired.
+pub fn decode_claims(jwt_secret: &str, token: &str) -> Option<Claims> {
+    let decoding_key = DecodingKey::from_secret(jwt_secret.as_ref());
+
+    decode::<Claims>(token, &decoding_key, &Validation::default())
+        .map(|token_data| token_data.claims)
+        .ok()
+}
@@ -0,0 +1,276 @@
+use serde::Deserialize;
+use sqlx::types::Uuid;
+
+use crate::{
+    database::DbPool,
+    ipfs::IpfsBackend,
+    jobs::{JobKind, enqueue},
+    models::{
+        app::App,
+        deployment::{Deployment, DeploymentStatus},
+        deployment_alias::DeploymentAlias,
+    },
+    payloads::{
+        app::UpdateAppPayload,
+        deployment::{CreateDeploymentPayload, UpdateDeploymentPayload},
+        deployment_alias::CreateDeploymentAliasPayload,
+    },
+    replication::{schedule_pins, select_nodes},
+    server::ServerState,
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DeployConfig {
+    pub working_dir: String,
+    pub max_archive_size: u64,
+    pub max_unpacked_size: u64,
+    pub max_files: u64,
+    pub pin_timeout_seconds: u64,
+}
+
+/// Publishes the deployment CID on the app IPNS key and marks it as the live deployment.
+pub async fn publish_deployment(
+    db_pool: &DbPool,
+    ipfs: &dyn IpfsBackend,
+    app: &App,
+    key_name: &str,
+    deployment: &Deployment,
+) -> Result<Deployment, String> {
+    let ipns_result = ipfs.name_publish(key_name, &deployment.cid).await?;
+    println!(
+        "[Deploy] App \"{}\" published on IPNS ({} -> {})",
+        app.name, ipns_result.name, ipns_result.value
+    );
+
+    app.update(
+        db_pool,
+        &UpdateAppPayload {
+            team_id: None,
+            name: None,
+            key_name: None,
+            ipns_name: Some(ipns_result.name.clone()),
+            replication_factor: None,
+        },
+    )
+    .await?;
+    deployment.mark_deployed(db_pool).await
+}
+
+/// Puts an earlier deployment of the app back online under a new deployment record.
+pub async fn rollback_app(
+    state: &ServerState,
+    app: &App,
+    source: &Deployment,
+) -> Result<Deployment, String> {
+    if source.app_id != app.id {
+        return Err(format!(
+            "Deployment {} does not belong to app {}",
+            source.id, app.name
+        ));
+    }
+    if !matches!(
+        source.status,
+        DeploymentStatus::DEPLOYED | DeploymentStatus::SUPERSEDED
+    ) {
+        return Err(format!(
+            "Deployment {} was never published and cannot be restored",
+            source.id
+        ));
+    }
+    if app.key_name.is_none() {
+        return Err(format!("App {} has no IPNS key", app.name));
+    }
+
+    let deployment = Deployment::create(
+        &state.db_pool,
+        &CreateDeploymentPayload {
+            app_id: app.id.to_string(),
+            cid: source.cid.clone(),
+            file_count: source.file_count,
+            total_size: source.total_size,
+            source_deployment_id: Some(source.id.to_string()),
+            site_rules: source.site_rules.as_ref().map(|rules| rules.0.clone()),
+        },
+    )
+    .await?;
+    println!(
+        "[Deploy] Rolling back app \"{}\" to deployment {} (CID: {})",
+        app.name, source.id, source.cid
+    );
+    start_publication(state, app, &deployment).await
+}
+
+/// Publishes a preview deployment on the app IPNS name.
+pub async fn promote_deployment(
+    state: &ServerState,
+    app: &App,
+    deployment: &Deployment,
+) -> Result<Deployment, String> {
+    if deployment.app_id != app.id {
+        return Err(format!(
+            "Deployment {} does not belong to app {}",
+            deployment.id, app.name
+        ));
+    }
+    if !matches!(deployment.status, DeploymentStatus::PREVIEW) {
+        return Err(format!(
+            "Deployment {} is not a preview and cannot be promoted",
+            deployment.id
+        ));
+    }
+    if app.key_name.is_none() {
+        return Err(format!("App {} has no IPNS key", app.name));
+    }
+
+    println!(
+        "[Deploy] Promoting preview {} of app \"{}\" (CID: {})",
+        deployment.id, app.name, deployment.cid
+    );
+    start_publication(state, app, deployment).await
+}
+
+/// Queues the IPNS publication of a deployment and its pinning on nodes.
+pub async fn start_publication(
+    state: &ServerState,
+    app: &App,
+    deployment: &Deployment,
+) -> Result<Deployment, String> {
+    let deployment = deployment
+        .update(
+            &state.db_pool,
+            &UpdateDeploymentPayload {
+                app_id: None,
+                cid: None,
+                status: Some(DeploymentStatus::PUBLISHING),
+            },
+        )
+        .await?;
+
+    // Pins exist before the publication can mark the deployment live, so that it is never seen
+    // settled without them. The replication reconciler tops up replicas later if not enough
+    // nodes are available
+    match select_nodes(
+        state,
+        app,
+        &deployment,
+        app.replication_factor.max(0) as usize,
+        &[],
+    )
+    .await
+    {
+        Ok(nodes) => {
+            if nodes.is_empty() {
+                println!("[Deploy] No active node found to pin app \"{}\"", app.name);
+            }
+            schedule_pins(state, &deployment, &nodes).await;
+        }
+        Err(e) => eprintln!("[Deploy] Error in retrieving nodes: {}", e),
+    }
+
+    if let Err(e) = enqueue(
+        &state.redis_client,
+        JobKind::PublishIpns {
+            deployment_id: deployment.id.to_string(),
+        },
+    )
+    .await
+    {
+        let _ = deployment
+            .update(
+                &state.db_pool,
+                &UpdateDeploymentPayload {
+                    app_id: None,
+                    cid: None,
+                    status: Some(DeploymentStatus::FAILED),
+                },
+            )
+            .await;
+        return Err(format!("Error in IPNS publication scheduling: {}", e));
+    }
+
+    Ok(deployment)
+}
+
+/// Tells deployment ids from alias names in release references.
+pub fn is_deployment_id(reference: &str) -> bool {
+    Uuid::parse_str(reference).is_ok()
+}
+
+/// Alias names go in URLs after the app name, and must not be mistaken for deployment ids.
+pub fn validate_alias_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("Alias names must be 1 to 64 characters long".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    {
+        return Err(format!(
+            "Invalid alias name {}: only letters, digits, '.', '-' and '_' are allowed",
+            name
+        ));
+    }
+    if is_deployment_id(name) {
+        return Err(format!("Alias name {} looks like a deployment id", name));
+    }
+
+    Ok(())
+}
+
+/// Attaches a release alias to a deployment of the app.
+pub async fn alias_deployment(
+    db_pool: &DbPool,
+    app: &App,
+    deployment: &Deployment,
+    name: &str,
+) -> Result<DeploymentAlias, String> {
+    validate_alias_name(name)?;
+    if deployment.app_id != app.id {
+        return Err(format!(
+            "Deployment {} does not belong to app {}",
+            deployment.id, app.name
+        ));
+    }
+    if !deployment.is_servable() {
+        return Err(format!(
+            "Deployment {} has no content to serve and cannot be aliased",
+            deployment.id
+        ));
+    }
+
+    let alias = DeploymentAlias::upsert(
+        db_pool,
+        &CreateDeploymentAliasPayload {
+            app_id: app.id.to_string(),
+            deployment_id: deployment.id.to_string(),
+            name: name.to_string(),
+        },
+    )
+    .await?;
+    println!(
+        "[Deploy] Alias \"{}\" of app \"{}\" now points to deployment {}",
+        alias.name, app.name, deployment.id
+    );
+
+    Ok(alias)
+}
+
+/// Finds a deployment of the app from its id or one of its aliases.
+pub async fn find_deployment_by_reference(
+    db_pool: &DbPool,
+    app: &App,
+    reference: &str,
+) -> Result<Option<Deployment>, String> {
+    let deployment_id = match Uuid::parse_str(reference) {
+        Ok(deployment_id) => deployment_id,
+        Err(_) => match DeploymentAlias::find_by_name(db_pool, &app.id, reference).await? {
+            Some(alias) => alias.deployment_id,
+            None => return Ok(None),
+        },
+    };
+
+    match Deployment::find_by_id(db_pool, &deployment_id.to_string()).await {
+        Ok(deployment) if deployment.app_id == app.id => Ok(Some(deployment)),
+        _ => Ok(None),
+    }
+}
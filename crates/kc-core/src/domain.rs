@@ -0,0 +1,174 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use reqwest::{Client, redirect};
+use serde::Deserialize;
+
+use crate::models::{app::App, domain::Domain};
+
+// File the owner serves on the domain, containing the verification token
+pub const VERIFICATION_PATH: &str = "/.well-known/keyston-domain-verification";
+// Prefix of the TXT record holding the verification token
+const CHALLENGE_RECORD_PREFIX: &str = "_keyston-challenge";
+// DNS record type of TXT answers
+const DNS_TYPE_TXT: u16 = 16;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DomainConfig {
+    /// DNS-over-HTTPS endpoint answering JSON queries
+    pub dns_resolver: String,
+    pub verification_timeout_seconds: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct DnsResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DnsAnswer>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DnsAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+/// Normalizes a hostname given by a user, refusing IP addresses and single labels.
+pub fn validate_hostname(hostname: &str) -> Result<String, String> {
+    let hostname = hostname.trim().trim_end_matches('.').to_ascii_lowercase();
+    if hostname.len() > 253 || hostname.parse::<IpAddr>().is_ok() {
+        return Err(format!("Invalid hostname: {}", hostname));
+    }
+
+    let labels = hostname.split('.').collect::<Vec<_>>();
+    let valid_label = |label: &&str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if labels.len() < 2 || !labels.iter().all(valid_label) || hostname.ends_with(".localhost") {
+        return Err(format!("Invalid hostname: {}", hostname));
+    }
+
+    Ok(hostname)
+}
+
+/// Extracts the hostname of a `Host` header value, without port.
+pub fn host_name(host: &str) -> Option<String> {
+    let hostname = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    validate_hostname(hostname).ok()
+}
+
+/// Returns the app label of a `{label}.{base_domain}` hostname.
+pub fn subdomain_label<'a>(hostname: &'a str, base_domain: &str) -> Option<&'a str> {
+    let base_domain = base_domain.trim_end_matches('.');
+    let label = hostname.strip_suffix(base_domain)?.strip_suffix('.')?;
+
+    // Nested subdomains are not apps
+    if label.is_empty() || label.contains('.') {
+        return None;
+    }
+    Some(label)
+}
+
+/// Checks whether an app name can be used as a subdomain label.
+pub fn is_subdomain_label(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 63
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Checks that the owner of the domain published its verification token.
+///
+/// The token is looked for on the well-known HTTP path of the domain, then in a
+/// `_keyston-challenge` TXT record. A DNSLink record pointing to the app IPNS name is also
+/// accepted as proof of ownership.
+pub async fn verify_domain(
+    config: &DomainConfig,
+    app: &App,
+    domain: &Domain,
+) -> Result<(), String> {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(config.verification_timeout_seconds))
+        .redirect(redirect::Policy::none())
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let well_known_url = format!("http://{}{}", domain.hostname, VERIFICATION_PATH);
+    if let Ok(response) = client.get(&well_known_url).send().await
+        && response.status().is_success()
+        && let Ok(body) = response.text().await
+        && body.trim() == domain.verification_token
+    {
+        return Ok(());
+    }
+
+    let challenge_name = format!("{}.{}", CHALLENGE_RECORD_PREFIX, domain.hostname);
+    let challenge_records = txt_records(&client, config, &challenge_name).await?;
+    if challenge_records
+        .iter()
+        .any(|record| record == &domain.verification_token)
+    {
+        return Ok(());
+    }
+
+    if let Some(ipns_name) = &app.ipns_name {
+        let dnslink = format!("dnslink=/ipns/{}", ipns_name);
+        let dnslink_records =
+            txt_records(&client, config, &format!("_dnslink.{}", domain.hostname)).await?;
+        if dnslink_records.iter().any(|record| record == &dnslink) {
+            return Ok(());
+        }
+    }
+
+    Err(format!(
+        "Verification token of {} not found on {} nor in the {} TXT record",
+        domain.hostname, VERIFICATION_PATH, challenge_name
+    ))
+}
+
+async fn txt_records(
+    client: &Client,
+    config: &DomainConfig,
+    name: &str,
+) -> Result<Vec<String>, String> {
+    let response = client
+        .get(&config.dns_resolver)
+        .query(&[("name", name), ("type", "TXT")])
+        .header("Accept", "application/dns-json")
+        .send()
+        .await
+        .map_err(|e| format!("DNS resolver error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("DNS resolver error: status={}", response.status()));
+    }
+
+    let dns_response = response
+        .json::<DnsResponse>()
+        .await
+        .map_err(|e| format!("Invalid DNS resolver answer: {}", e))?;
+
+    Ok(dns_response
+        .answer
+        .into_iter()
+        .filter(|answer| answer.record_type == DNS_TYPE_TXT)
+        .map(|answer| txt_value(&answer.data))
+        .collect())
+}
+
+/// Joins the quoted strings a long TXT record is split into.
+fn txt_value(data: &str) -> String {
+    if !data.starts_with('"') {
+        return data.to_string();
+    }
+    data.split('"').skip(1).step_by(2).collect()
+}
@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgListener, types::Uuid};
+use tokio::sync::broadcast;
+
+use crate::{
+    database::DbPool,
+    models::{
+        deployment::{Deployment, DeploymentStatus},
+        deployment_node::{DeploymentNode, PinStatus},
+        node::NodeStatus,
+    },
+};
+
+/// Postgres channel fed by the status triggers on deployments and deployments_nodes.
+const DEPLOYMENT_EVENTS_CHANNEL: &str = "deployment_events";
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeploymentEvent {
+    Deployment {
+        deployment_id: String,
+        status: DeploymentStatus,
+    },
+    Pin {
+        deployment_id: String,
+        deployment_node_id: String,
+        node_id: String,
+        status: PinStatus,
+    },
+    /// Sent once the deployment and all of its pins have reached a final status.
+    Settled {
+        deployment_id: String,
+        status: DeploymentStatus,
+    },
+}
+
+impl DeploymentEvent {
+    pub fn deployment_id(&self) -> &str {
+        match self {
+            DeploymentEvent::Deployment { deployment_id, .. }
+            | DeploymentEvent::Pin { deployment_id, .. }
+            | DeploymentEvent::Settled { deployment_id, .. } => deployment_id,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeploymentEvent::Deployment { .. } => "deployment",
+            DeploymentEvent::Pin { .. } => "pin",
+            DeploymentEvent::Settled { .. } => "settled",
+        }
+    }
+}
+
+pub type EventBus = broadcast::Sender<DeploymentEvent>;
+
+pub fn create_event_bus() -> EventBus {
+    broadcast::channel(EVENT_BUS_CAPACITY).0
+}
+
+/// Sent by the health checker, once the change is recorded in the node status history.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NodeEvent {
+    Status {
+        node_id: String,
+        status: NodeStatus,
+        previous_status: NodeStatus,
+        last_seen: Option<i64>,
+    },
+}
+
+pub type NodeEventBus = broadcast::Sender<NodeEvent>;
+
+pub fn create_node_event_bus() -> NodeEventBus {
+    broadcast::channel(EVENT_BUS_CAPACITY).0
+}
+
+/// Relays the status notifications sent by Postgres to the event bus.
+pub async fn start_event_listener(db_pool: &DbPool, event_bus: EventBus) -> Result<(), String> {
+    let mut listener = PgListener::connect_with(db_pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    listener
+        .listen(DEPLOYMENT_EVENTS_CHANNEL)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tokio::spawn(async move {
+        loop {
+            let notification = match listener.recv().await {
+                Ok(notification) => notification,
+                Err(e) => {
+                    // The listener reconnects on its own on the next call
+                    eprintln!("[Events] Listener error: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            match serde_json::from_str::<DeploymentEvent>(notification.payload()) {
+                // Sending only fails when nobody is subscribed
+                Ok(event) => {
+                    let _ = event_bus.send(event);
+                }
+                Err(e) => eprintln!("[Events] Malformed notification: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Current status of a deployment and of its pins, as a list of events.
+pub async fn deployment_snapshot(
+    db_pool: &DbPool,
+    deployment_id: &Uuid,
+) -> Result<Vec<DeploymentEvent>, String> {
+    let deployment = Deployment::find_by_id(db_pool, &deployment_id.to_string()).await?;
+    let pins = DeploymentNode::find_by_deployment_id(db_pool, deployment_id).await?;
+
+    let mut events = vec![DeploymentEvent::Deployment {
+        deployment_id: deployment.id.to_string(),
+        status: deployment.status,
+    }];
+    for pin in &pins {
+        events.push(DeploymentEvent::Pin {
+            deployment_id: deployment.id.to_string(),
+            deployment_node_id: pin.id.to_string(),
+            node_id: pin.node_id.to_string(),
+            status: pin.status,
+        });
+    }
+    if is_settled(&deployment, &pins) {
+        events.push(DeploymentEvent::Settled {
+            deployment_id: deployment.id.to_string(),
+            status: deployment.status,
+        });
+    }
+
+    Ok(events)
+}
+
+/// Returns the terminal event if the deployment has settled.
+pub async fn settled_event(
+    db_pool: &DbPool,
+    deployment_id: &Uuid,
+) -> Result<Option<DeploymentEvent>, String> {
+    let deployment = Deployment::find_by_id(db_pool, &deployment_id.to_string()).await?;
+    let pins = DeploymentNode::find_by_deployment_id(db_pool, deployment_id).await?;
+
+    if !is_settled(&deployment, &pins) {
+        return Ok(None);
+    }
+    Ok(Some(DeploymentEvent::Settled {
+        deployment_id: deployment.id.to_string(),
+        status: deployment.status,
+    }))
+}
+
+fn is_settled(deployment: &Deployment, pins: &[DeploymentNode]) -> bool {
+    match deployment.status {
+        DeploymentStatus::PENDING | DeploymentStatus::PUBLISHING => false,
+        DeploymentStatus::FAILED | DeploymentStatus::ARCHIVED => true,
+        DeploymentStatus::DEPLOYED | DeploymentStatus::SUPERSEDED | DeploymentStatus::PREVIEW => {
+            !pins
+                .iter()
+                .any(|pin| matches!(pin.status, PinStatus::PINNING))
+        }
+    }
+}
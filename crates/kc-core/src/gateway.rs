@@ -0,0 +1,15 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct GatewayConfig {
+    /// Cache-Control of app URLs, whose content changes with each IPNS publication
+    pub ipns_cache_control: String,
+    /// Cache-Control of URLs addressing a CID, whose content never changes
+    pub immutable_cache_control: String,
+    /// How long the CID of an app is served from memory before the database is read again
+    pub app_cache_ttl_seconds: u64,
+    /// Domain under which each app is served on its own `{app_name}.{base_domain}` origin
+    pub base_domain: Option<String>,
+    /// Sends `/app/{app_name}` URLs to the app subdomain, once a base domain is set
+    pub redirect_to_subdomains: bool,
+}
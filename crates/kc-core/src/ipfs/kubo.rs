@@ -0,0 +1,389 @@
+use async_trait::async_trait;
+use futures_util::{TryStreamExt, stream};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use reqwest::{Body, Client, Response, multipart};
+use serde::Deserialize;
+use std::path::Path;
+use tokio::{fs, task};
+use tokio_util::io::ReaderStream;
+
+use crate::ipfs::{
+    ByteStream, IpfsBackend, IpfsEntry, IpfsError, IpnsPublishResponse, KeyInfo, entry_name,
+};
+
+// Kubo decodes multipart file names as query strings
+const IPFS_PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+// Unixfs link type of directories in `ls` answers
+const LINK_TYPE_DIRECTORY: i32 = 1;
+
+// Unixfs type of directories in `files/stat` answers
+const STAT_TYPE_DIRECTORY: &str = "directory";
+
+#[derive(Deserialize, Debug)]
+struct IPFSAddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct KeyListResponse {
+    #[serde(rename = "Keys")]
+    keys: Vec<KeyInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+struct NameResolveResponse {
+    #[serde(rename = "Path")]
+    path: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct LsResponse {
+    #[serde(rename = "Objects")]
+    objects: Vec<LsObject>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LsObject {
+    #[serde(rename = "Links")]
+    links: Vec<LsLink>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FilesStatResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+    #[serde(rename = "Size")]
+    size: u64,
+    #[serde(rename = "Type")]
+    object_type: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct LsLink {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Hash")]
+    hash: String,
+    #[serde(rename = "Size")]
+    size: u64,
+    #[serde(rename = "Type")]
+    link_type: i32,
+}
+
+/// A path of the site directory to add.
+enum SiteEntry {
+    Directory(String),
+    File { path: String, size: u64 },
+}
+
+/// Talks to a Kubo daemon through its RPC API.
+pub struct KuboBackend {
+    host: String,
+    client: Client,
+}
+
+impl KuboBackend {
+    pub fn new(host: &str) -> Self {
+        KuboBackend {
+            host: host.to_string(),
+            client: Client::new(),
+        }
+    }
+
+    async fn call(&self, command: &str, args: &[(&str, &str)]) -> Result<Response, IpfsError> {
+        self.client
+            .post(format!("{}/api/v0/{}", self.host, command))
+            .query(args)
+            .send()
+            .await
+            .map_err(|e| IpfsError::Backend(e.to_string()))
+    }
+
+    /// Kubo answers errors on content lookups with a 500, read as a missing path.
+    async fn lookup(&self, command: &str, arg: &str) -> Result<Response, IpfsError> {
+        let resp = self.call(command, &[("arg", arg)]).await?;
+        if !resp.status().is_success() {
+            let error_text = resp.text().await.unwrap_or_default();
+            return Err(IpfsError::NotFound(error_text));
+        }
+
+        Ok(resp)
+    }
+
+    /// Offline calls never fetch from the network, so the satellite only serves what it stores.
+    async fn local_stream(&self, command: &str, cid: &str) -> Result<ByteStream, IpfsError> {
+        let resp = self
+            .call(command, &[("arg", cid), ("offline", "true")])
+            .await?;
+        if !resp.status().is_success() {
+            let error_text = resp.text().await.unwrap_or_default();
+            return Err(IpfsError::NotFound(error_text));
+        }
+
+        Ok(Box::pin(
+            resp.bytes_stream()
+                .map_err(|e| IpfsError::Backend(e.to_string())),
+        ))
+    }
+}
+
+async fn expect_success(resp: Response, action: &str) -> Result<Response, IpfsError> {
+    if !resp.status().is_success() {
+        let error_text = resp.text().await.unwrap_or_default();
+        return Err(IpfsError::Backend(format!(
+            "{} failed: {}",
+            action, error_text
+        )));
+    }
+
+    Ok(resp)
+}
+
+#[async_trait]
+impl IpfsBackend for KuboBackend {
+    async fn add(&self, site_dir: &Path) -> Result<String, IpfsError> {
+        let root = site_dir.to_path_buf();
+        let entries = task::spawn_blocking(move || {
+            let mut entries = Vec::new();
+            collect_site_entries(&root, Path::new(""), &mut entries).map(|_| entries)
+        })
+        .await
+        .map_err(|e| IpfsError::Backend(e.to_string()))?
+        .map_err(|e| IpfsError::Backend(e.to_string()))?;
+
+        // Directories must precede their content for Kubo to rebuild the tree
+        let mut form = multipart::Form::new();
+        for entry in entries {
+            let part = match entry {
+                SiteEntry::Directory(path) => multipart::Part::bytes(Vec::new())
+                    .file_name(encode_ipfs_path(&path))
+                    .mime_str("application/x-directory")
+                    .map_err(|e| IpfsError::Backend(e.to_string()))?,
+                SiteEntry::File { path, size } => {
+                    // Opened once the upload reaches it, a site may hold more files than the
+                    // process can keep open
+                    let content = stream::once(fs::File::open(site_dir.join(&path)))
+                        .map_ok(ReaderStream::new)
+                        .try_flatten();
+                    multipart::Part::stream_with_length(Body::wrap_stream(content), size)
+                        .file_name(encode_ipfs_path(&path))
+                }
+            };
+            form = form.part("file", part);
+        }
+
+        let resp = self
+            .client
+            .post(format!("{}/api/v0/add?wrap-with-directory=true", self.host))
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| IpfsError::Backend(e.to_string()))?;
+        let resp = expect_success(resp, "Add").await?;
+
+        // Kubo answers one JSON line per added entry, the wrapping directory comes last
+        let body = resp
+            .text()
+            .await
+            .map_err(|e| IpfsError::Backend(e.to_string()))?;
+        let root_line = match body.lines().rev().find(|line| !line.trim().is_empty()) {
+            Some(line) => line,
+            None => return Err(IpfsError::Backend("Empty add response".to_string())),
+        };
+        let kubo_resp: IPFSAddResponse =
+            serde_json::from_str(root_line).map_err(|e| IpfsError::Backend(e.to_string()))?;
+
+        Ok(kubo_resp.hash)
+    }
+
+    async fn cat(
+        &self,
+        path: &str,
+        offset: u64,
+        length: Option<u64>,
+    ) -> Result<ByteStream, IpfsError> {
+        let offset = offset.to_string();
+        let length = length.map(|length| length.to_string());
+        let mut args = vec![("arg", path), ("offset", offset.as_str())];
+        if let Some(length) = &length {
+            args.push(("length", length));
+        }
+
+        let resp = self.call("cat", &args).await?;
+        if !resp.status().is_success() {
+            let error_text = resp.text().await.unwrap_or_default();
+            return Err(IpfsError::NotFound(error_text));
+        }
+
+        Ok(Box::pin(
+            resp.bytes_stream()
+                .map_err(|e| IpfsError::Backend(e.to_string())),
+        ))
+    }
+
+    async fn ls(&self, path: &str) -> Result<Vec<IpfsEntry>, IpfsError> {
+        let resp = self.lookup("ls", path).await?;
+        let ls: LsResponse = resp
+            .json()
+            .await
+            .map_err(|e| IpfsError::Backend(e.to_string()))?;
+
+        Ok(ls
+            .objects
+            .into_iter()
+            .flat_map(|object| object.links)
+            .map(|link| IpfsEntry {
+                name: link.name,
+                cid: link.hash,
+                size: link.size,
+                is_dir: link.link_type == LINK_TYPE_DIRECTORY,
+            })
+            .collect())
+    }
+
+    async fn stat(&self, path: &str) -> Result<IpfsEntry, IpfsError> {
+        // MFS commands take immutable content under its `/ipfs/` namespace
+        let ipfs_path = if path.starts_with("/ipfs/") {
+            path.to_string()
+        } else {
+            format!("/ipfs/{}", path)
+        };
+        let resp = self.lookup("files/stat", &ipfs_path).await?;
+        let stat: FilesStatResponse = resp
+            .json()
+            .await
+            .map_err(|e| IpfsError::Backend(e.to_string()))?;
+
+        Ok(IpfsEntry {
+            name: entry_name(path),
+            cid: stat.hash,
+            size: stat.size,
+            is_dir: stat.object_type == STAT_TYPE_DIRECTORY,
+        })
+    }
+
+    async fn pin(&self, cid: &str) -> Result<(), IpfsError> {
+        let resp = self.call("pin/add", &[("arg", cid)]).await?;
+        expect_success(resp, "Pin").await?;
+
+        Ok(())
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<(), IpfsError> {
+        let resp = self.call("pin/rm", &[("arg", cid)]).await?;
+        if !resp.status().is_success() {
+            let error_text = resp.text().await.unwrap_or_default();
+            if error_text.contains("not pinned") {
+                return Ok(());
+            }
+            return Err(IpfsError::Backend(format!("Unpin failed: {}", error_text)));
+        }
+
+        Ok(())
+    }
+
+    async fn key_gen(&self, name: &str) -> Result<KeyInfo, IpfsError> {
+        let resp = self
+            .call("key/gen", &[("arg", name), ("type", "ed25519")])
+            .await?;
+        let resp = expect_success(resp, "Key gen").await?;
+
+        resp.json()
+            .await
+            .map_err(|e| IpfsError::Backend(e.to_string()))
+    }
+
+    async fn key_list(&self) -> Result<Vec<KeyInfo>, IpfsError> {
+        let resp = self.call("key/list", &[]).await?;
+        let resp = expect_success(resp, "Key list").await?;
+        let key_list: KeyListResponse = resp
+            .json()
+            .await
+            .map_err(|e| IpfsError::Backend(e.to_string()))?;
+
+        Ok(key_list.keys)
+    }
+
+    async fn key_rm(&self, name: &str) -> Result<(), IpfsError> {
+        let resp = self.call("key/rm", &[("arg", name)]).await?;
+        expect_success(resp, "Key removal").await?;
+
+        Ok(())
+    }
+
+    async fn name_publish(
+        &self,
+        key_name: &str,
+        cid: &str,
+    ) -> Result<IpnsPublishResponse, IpfsError> {
+        let ipfs_path = format!("/ipfs/{}", cid);
+        let resp = self
+            .call("name/publish", &[("key", key_name), ("arg", &ipfs_path)])
+            .await?;
+        let resp = expect_success(resp, "Publishing").await?;
+
+        resp.json()
+            .await
+            .map_err(|e| IpfsError::Backend(e.to_string()))
+    }
+
+    async fn name_resolve(&self, name: &str) -> Result<String, IpfsError> {
+        let resp = self.lookup("name/resolve", name).await?;
+        let resolved: NameResolveResponse = resp
+            .json()
+            .await
+            .map_err(|e| IpfsError::Backend(e.to_string()))?;
+
+        match resolved.path.strip_prefix("/ipfs/") {
+            Some(cid) => Ok(cid.to_string()),
+            None => Err(IpfsError::Backend(format!(
+                "Unexpected IPNS path: {}",
+                resolved.path
+            ))),
+        }
+    }
+
+    async fn block_get(&self, cid: &str) -> Result<ByteStream, IpfsError> {
+        self.local_stream("block/get", cid).await
+    }
+
+    async fn dag_export(&self, cid: &str) -> Result<ByteStream, IpfsError> {
+        self.local_stream("dag/export", cid).await
+    }
+}
+
+fn encode_ipfs_path(path: &str) -> String {
+    utf8_percent_encode(path, IPFS_PATH_ENCODE_SET).to_string()
+}
+
+fn collect_site_entries(
+    root: &Path,
+    relative: &Path,
+    entries: &mut Vec<SiteEntry>,
+) -> std::io::Result<()> {
+    let mut children = std::fs::read_dir(root.join(relative))?.collect::<Result<Vec<_>, _>>()?;
+    children.sort_by_key(|child| child.file_name());
+
+    for child in children {
+        let path = relative.join(child.file_name());
+        let name = path.to_string_lossy().to_string();
+        if child.file_type()?.is_dir() {
+            entries.push(SiteEntry::Directory(name));
+            collect_site_entries(root, &path, entries)?;
+        } else {
+            entries.push(SiteEntry::File {
+                path: name,
+                size: child.metadata()?.len(),
+            });
+        }
+    }
+
+    Ok(())
+}
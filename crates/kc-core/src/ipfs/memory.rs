@@ -0,0 +1,451 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::task;
+use uuid::Uuid;
+
+use crate::ipfs::{
+    ByteStream, IpfsBackend, IpfsEntry, IpfsError, IpnsPublishResponse, KeyInfo, entry_name,
+    split_ipfs_path,
+};
+
+// Mirrors the unixfs chunk size so streams behave like Kubo ones
+const CHUNK_SIZE: usize = 256 * 1024;
+
+#[derive(Debug, Clone)]
+enum MemoryObject {
+    File(Vec<u8>),
+    Directory(BTreeMap<String, String>),
+}
+
+impl MemoryObject {
+    /// Content address of the object, stable across runs.
+    fn cid(&self) -> String {
+        let mut hasher = Sha256::new();
+        match self {
+            MemoryObject::File(data) => {
+                hasher.update(b"file\0");
+                hasher.update(data);
+            }
+            MemoryObject::Directory(links) => {
+                hasher.update(b"dir\0");
+                for (name, cid) in links {
+                    hasher.update(name.as_bytes());
+                    hasher.update(b"\0");
+                    hasher.update(cid.as_bytes());
+                    hasher.update(b"\0");
+                }
+            }
+        }
+
+        format!("mem{}", hex::encode(hasher.finalize()))
+    }
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    objects: HashMap<String, MemoryObject>,
+    pins: HashSet<String>,
+    // Key name to key id
+    keys: HashMap<String, String>,
+    // Key id to published CID
+    names: HashMap<String, String>,
+}
+
+impl MemoryStore {
+    fn resolve(&self, path: &str) -> Result<(String, &MemoryObject), IpfsError> {
+        let (cid, segments) = split_ipfs_path(path);
+        let mut current_cid = cid.to_string();
+        let mut object = self
+            .objects
+            .get(cid)
+            .ok_or_else(|| IpfsError::NotFound(path.to_string()))?;
+
+        for segment in segments {
+            let child_cid = match object {
+                MemoryObject::Directory(links) => links.get(segment),
+                MemoryObject::File(_) => None,
+            }
+            .ok_or_else(|| IpfsError::NotFound(path.to_string()))?;
+            object = self
+                .objects
+                .get(child_cid)
+                .ok_or_else(|| IpfsError::NotFound(path.to_string()))?;
+            current_cid = child_cid.clone();
+        }
+
+        Ok((current_cid, object))
+    }
+
+    fn size(&self, object: &MemoryObject) -> u64 {
+        match object {
+            MemoryObject::File(data) => data.len() as u64,
+            MemoryObject::Directory(links) => links
+                .values()
+                .filter_map(|cid| self.objects.get(cid))
+                .map(|child| self.size(child))
+                .sum(),
+        }
+    }
+}
+
+/// Keeps everything in process memory, for local development without a Kubo daemon.
+///
+/// Content is lost on restart and CIDs are not real IPFS CIDs, so nodes cannot fetch it.
+#[derive(Default)]
+pub struct MemoryBackend {
+    store: Mutex<MemoryStore>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        MemoryBackend::default()
+    }
+
+    fn store(&self) -> std::sync::MutexGuard<'_, MemoryStore> {
+        // The store stays consistent even if a holder panicked
+        self.store
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl IpfsBackend for MemoryBackend {
+    async fn add(&self, site_dir: &Path) -> Result<String, IpfsError> {
+        let root = site_dir.to_path_buf();
+        let (root_cid, objects) = task::spawn_blocking(move || {
+            let mut objects = HashMap::new();
+            read_tree(&root, &mut objects).map(|cid| (cid, objects))
+        })
+        .await
+        .map_err(|e| IpfsError::Backend(e.to_string()))?
+        .map_err(|e| IpfsError::Backend(e.to_string()))?;
+
+        let mut store = self.store();
+        store.objects.extend(objects);
+        store.pins.insert(root_cid.clone());
+
+        Ok(root_cid)
+    }
+
+    async fn cat(
+        &self,
+        path: &str,
+        offset: u64,
+        length: Option<u64>,
+    ) -> Result<ByteStream, IpfsError> {
+        let store = self.store();
+        let data = match store.resolve(path)? {
+            (_, MemoryObject::File(data)) => data,
+            (_, MemoryObject::Directory(_)) => {
+                return Err(IpfsError::Backend(format!("{} is a directory", path)));
+            }
+        };
+
+        let start = (offset as usize).min(data.len());
+        let end = match length {
+            Some(length) => start.saturating_add(length as usize).min(data.len()),
+            None => data.len(),
+        };
+        let chunks: Vec<Result<Bytes, IpfsError>> = data[start..end]
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect();
+
+        Ok(Box::pin(stream::iter(chunks)))
+    }
+
+    async fn ls(&self, path: &str) -> Result<Vec<IpfsEntry>, IpfsError> {
+        let store = self.store();
+        let links = match store.resolve(path)? {
+            (_, MemoryObject::Directory(links)) => links,
+            (_, MemoryObject::File(_)) => return Ok(Vec::new()),
+        };
+
+        Ok(links
+            .iter()
+            .filter_map(|(name, cid)| {
+                store.objects.get(cid).map(|object| IpfsEntry {
+                    name: name.clone(),
+                    cid: cid.clone(),
+                    size: store.size(object),
+                    is_dir: matches!(object, MemoryObject::Directory(_)),
+                })
+            })
+            .collect())
+    }
+
+    async fn stat(&self, path: &str) -> Result<IpfsEntry, IpfsError> {
+        let store = self.store();
+        let (cid, object) = store.resolve(path)?;
+
+        Ok(IpfsEntry {
+            name: entry_name(path),
+            cid,
+            size: store.size(object),
+            is_dir: matches!(object, MemoryObject::Directory(_)),
+        })
+    }
+
+    async fn pin(&self, cid: &str) -> Result<(), IpfsError> {
+        let mut store = self.store();
+        let (cid, _) = store.resolve(cid)?;
+        store.pins.insert(cid);
+
+        Ok(())
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<(), IpfsError> {
+        self.store().pins.remove(cid);
+
+        Ok(())
+    }
+
+    async fn key_gen(&self, name: &str) -> Result<KeyInfo, IpfsError> {
+        let mut store = self.store();
+        if store.keys.contains_key(name) {
+            return Err(IpfsError::Backend(format!(
+                "Key \"{}\" already exists",
+                name
+            )));
+        }
+
+        let id = format!("k51mem{}", Uuid::new_v4().simple());
+        store.keys.insert(name.to_string(), id.clone());
+
+        Ok(KeyInfo {
+            name: name.to_string(),
+            id,
+        })
+    }
+
+    async fn key_list(&self) -> Result<Vec<KeyInfo>, IpfsError> {
+        Ok(self
+            .store()
+            .keys
+            .iter()
+            .map(|(name, id)| KeyInfo {
+                name: name.clone(),
+                id: id.clone(),
+            })
+            .collect())
+    }
+
+    async fn key_rm(&self, name: &str) -> Result<(), IpfsError> {
+        let mut store = self.store();
+        match store.keys.remove(name) {
+            Some(id) => {
+                store.names.remove(&id);
+                Ok(())
+            }
+            None => Err(IpfsError::NotFound(format!("Key \"{}\"", name))),
+        }
+    }
+
+    async fn name_publish(
+        &self,
+        key_name: &str,
+        cid: &str,
+    ) -> Result<IpnsPublishResponse, IpfsError> {
+        let mut store = self.store();
+        let id = match store.keys.get(key_name) {
+            Some(id) => id.clone(),
+            None => return Err(IpfsError::NotFound(format!("Key \"{}\"", key_name))),
+        };
+        store.names.insert(id.clone(), cid.to_string());
+
+        Ok(IpnsPublishResponse {
+            name: id,
+            value: format!("/ipfs/{}", cid),
+        })
+    }
+
+    async fn name_resolve(&self, name: &str) -> Result<String, IpfsError> {
+        let name = name.strip_prefix("/ipns/").unwrap_or(name);
+        match self.store().names.get(name) {
+            Some(cid) => Ok(cid.clone()),
+            None => Err(IpfsError::NotFound(format!("IPNS name {}", name))),
+        }
+    }
+
+    async fn block_get(&self, cid: &str) -> Result<ByteStream, IpfsError> {
+        self.store().resolve(cid)?;
+        Err(IpfsError::Unsupported(
+            "the memory backend stores no IPLD blocks".to_string(),
+        ))
+    }
+
+    async fn dag_export(&self, cid: &str) -> Result<ByteStream, IpfsError> {
+        self.store().resolve(cid)?;
+        Err(IpfsError::Unsupported(
+            "the memory backend stores no IPLD blocks".to_string(),
+        ))
+    }
+}
+
+/// Stores a directory tree bottom-up and returns the CID of its root.
+fn read_tree(dir: &Path, objects: &mut HashMap<String, MemoryObject>) -> std::io::Result<String> {
+    let mut children = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    children.sort_by_key(|child| child.file_name());
+
+    let mut links = BTreeMap::new();
+    for child in children {
+        let file_type = child.file_type()?;
+        let cid = if file_type.is_dir() {
+            read_tree(&child.path(), objects)?
+        } else if file_type.is_file() {
+            let object = MemoryObject::File(std::fs::read(child.path())?);
+            let cid = object.cid();
+            objects.insert(cid.clone(), object);
+            cid
+        } else {
+            continue;
+        };
+        links.insert(child.file_name().to_string_lossy().to_string(), cid);
+    }
+
+    let object = MemoryObject::Directory(links);
+    let cid = object.cid();
+    objects.insert(cid.clone(), object);
+
+    Ok(cid)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::TryStreamExt;
+
+    use crate::ipfs::cat_to_vec;
+
+    use super::*;
+
+    /// Adds a site made of the given files, directories created along their paths.
+    async fn add_site(ipfs: &MemoryBackend, files: &[(&str, &[u8])]) -> String {
+        let site_dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = site_dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        ipfs.add(site_dir.path()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn lists_and_reads_back_an_added_site() {
+        let ipfs = MemoryBackend::new();
+        let cid = add_site(
+            &ipfs,
+            &[
+                ("index.html", b"<h1>Home</h1>"),
+                ("css/site.css", b"body {}"),
+            ],
+        )
+        .await;
+
+        let entries = ipfs.ls(&cid).await.unwrap();
+        let listed: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.size, entry.is_dir))
+            .collect();
+        assert_eq!(listed, vec![("css", 7, true), ("index.html", 13, false)]);
+
+        let css = ipfs.ls(&format!("/ipfs/{}/css", cid)).await.unwrap();
+        assert_eq!(css.len(), 1);
+        assert_eq!(css[0].name, "site.css");
+
+        let index = cat_to_vec(&ipfs, &format!("{}/index.html", cid), None)
+            .await
+            .unwrap();
+        assert_eq!(index, b"<h1>Home</h1>");
+        let stylesheet = cat_to_vec(&ipfs, &format!("{}/css/site.css", cid), None)
+            .await
+            .unwrap();
+        assert_eq!(stylesheet, b"body {}");
+    }
+
+    #[tokio::test]
+    async fn reads_ranges_in_unixfs_sized_chunks() {
+        let ipfs = MemoryBackend::new();
+        let content = vec![7u8; CHUNK_SIZE * 2 + 10];
+        let cid = add_site(&ipfs, &[("blob.bin", &content)]).await;
+        let path = format!("{}/blob.bin", cid);
+
+        let chunks: Vec<Bytes> = ipfs
+            .cat(&path, 0, None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let sizes: Vec<_> = chunks.iter().map(|chunk| chunk.len()).collect();
+        assert_eq!(sizes, vec![CHUNK_SIZE, CHUNK_SIZE, 10]);
+
+        let range = cat_to_vec(&ipfs, &path, Some(0)).await.unwrap();
+        assert!(range.is_empty());
+        let range = ipfs.cat(&path, 5, Some(20)).await.unwrap();
+        let range: Vec<Bytes> = range.try_collect().await.unwrap();
+        assert_eq!(range.concat().len(), 20);
+        let tail = ipfs
+            .cat(&path, content.len() as u64 - 4, Some(100))
+            .await
+            .unwrap();
+        let tail: Vec<Bytes> = tail.try_collect().await.unwrap();
+        assert_eq!(tail.concat().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn gives_identical_content_the_same_cid() {
+        let ipfs = MemoryBackend::new();
+        let first = add_site(&ipfs, &[("index.html", b"same")]).await;
+        let second = add_site(&ipfs, &[("index.html", b"same")]).await;
+        let other = add_site(&ipfs, &[("index.html", b"other")]).await;
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[tokio::test]
+    async fn describes_files_and_directories() {
+        let ipfs = MemoryBackend::new();
+        let cid = add_site(&ipfs, &[("docs/guide.txt", b"read me")]).await;
+
+        let root = ipfs.stat(&cid).await.unwrap();
+        assert!(root.is_dir);
+        assert_eq!(root.name, cid);
+        let file = ipfs.stat(&format!("{}/docs/guide.txt", cid)).await.unwrap();
+        assert!(!file.is_dir);
+        assert_eq!((file.name.as_str(), file.size), ("guide.txt", 7));
+        let directory = ipfs.ls(&format!("{}/docs", cid)).await.unwrap();
+        assert_eq!(file.cid, directory[0].cid);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_paths_and_directory_reads() {
+        let ipfs = MemoryBackend::new();
+        let cid = add_site(&ipfs, &[("index.html", b"home")]).await;
+
+        assert!(matches!(
+            ipfs.ls(&format!("{}/missing", cid)).await,
+            Err(IpfsError::NotFound(_))
+        ));
+        assert!(matches!(
+            ipfs.cat(&format!("{}/index.html/child", cid), 0, None)
+                .await,
+            Err(IpfsError::NotFound(_))
+        ));
+        assert!(matches!(
+            ipfs.stat("memunknown").await,
+            Err(IpfsError::NotFound(_))
+        ));
+        assert!(matches!(
+            ipfs.cat(&cid, 0, None).await,
+            Err(IpfsError::Backend(_))
+        ));
+    }
+}
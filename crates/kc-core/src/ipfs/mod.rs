@@ -0,0 +1,149 @@
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::{Stream, TryStreamExt};
+use serde::Deserialize;
+use thiserror::Error;
+
+pub mod kubo;
+pub mod memory;
+
+pub use kubo::KuboBackend;
+pub use memory::MemoryBackend;
+
+#[derive(Debug, Error)]
+pub enum IpfsError {
+    #[error("[IPFS] Not found: {0}")]
+    NotFound(String),
+    #[error("[IPFS] {0}")]
+    Backend(String),
+    #[error("[IPFS] Unsupported: {0}")]
+    Unsupported(String),
+}
+
+impl From<IpfsError> for String {
+    fn from(error: IpfsError) -> Self {
+        error.to_string()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct KeyInfo {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Id")]
+    pub id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IpnsPublishResponse {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Value")]
+    pub value: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct IpfsEntry {
+    pub name: String,
+    pub cid: String,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, IpfsError>> + Send>>;
+
+/// Operations the satellite needs from an IPFS node.
+///
+/// Paths are a CID optionally followed by a path inside it, e.g. `bafy.../css/site.css`,
+/// with or without the `/ipfs/` prefix.
+#[async_trait]
+pub trait IpfsBackend: Send + Sync {
+    /// Adds a site directory and returns the CID of its root, pinned.
+    async fn add(&self, site_dir: &Path) -> Result<String, IpfsError>;
+    /// Streams a file from `offset`, up to `length` bytes when given.
+    async fn cat(
+        &self,
+        path: &str,
+        offset: u64,
+        length: Option<u64>,
+    ) -> Result<ByteStream, IpfsError>;
+    async fn ls(&self, path: &str) -> Result<Vec<IpfsEntry>, IpfsError>;
+    /// Describes the file or directory a path points to, named after its last segment.
+    async fn stat(&self, path: &str) -> Result<IpfsEntry, IpfsError>;
+    async fn pin(&self, cid: &str) -> Result<(), IpfsError>;
+    /// Succeeds if the CID was not pinned.
+    async fn unpin(&self, cid: &str) -> Result<(), IpfsError>;
+    async fn key_gen(&self, name: &str) -> Result<KeyInfo, IpfsError>;
+    async fn key_list(&self) -> Result<Vec<KeyInfo>, IpfsError>;
+    async fn key_rm(&self, name: &str) -> Result<(), IpfsError>;
+    async fn name_publish(
+        &self,
+        key_name: &str,
+        cid: &str,
+    ) -> Result<IpnsPublishResponse, IpfsError>;
+    /// Resolves an IPNS name to the CID it points to.
+    async fn name_resolve(&self, name: &str) -> Result<String, IpfsError>;
+    /// Streams the raw bytes of a block, only if the node already stores it.
+    async fn block_get(&self, cid: &str) -> Result<ByteStream, IpfsError>;
+    /// Streams the DAG under a CID as a CARv1 file, only if the node already stores it.
+    async fn dag_export(&self, cid: &str) -> Result<ByteStream, IpfsError>;
+}
+
+pub type IpfsClient = Arc<dyn IpfsBackend>;
+
+/// Builds the backend selected by `server.ipfs_backend`.
+pub fn create_ipfs_client(backend: &str, ipfs_host: &str) -> Result<IpfsClient, String> {
+    match backend {
+        "kubo" => Ok(Arc::new(KuboBackend::new(ipfs_host))),
+        "memory" => Ok(Arc::new(MemoryBackend::new())),
+        _ => Err(format!("Unsupported IPFS backend: {}", backend)),
+    }
+}
+
+pub async fn find_or_create_ipns_key(
+    ipfs: &dyn IpfsBackend,
+    app_name: &str,
+) -> Result<KeyInfo, String> {
+    let keys = ipfs.key_list().await?;
+    if let Some(key) = keys.into_iter().find(|k| k.name == app_name) {
+        println!("[IPNS] Key found for \"{}\"", app_name);
+        return Ok(key);
+    }
+
+    println!("[IPNS] Key not found, creation for \"{}\"", app_name);
+    Ok(ipfs.key_gen(app_name).await?)
+}
+
+/// Reads a whole file, for the small ones only.
+pub async fn cat_to_vec(
+    ipfs: &dyn IpfsBackend,
+    path: &str,
+    length: Option<u64>,
+) -> Result<Vec<u8>, IpfsError> {
+    ipfs.cat(path, 0, length)
+        .await?
+        .try_fold(Vec::new(), |mut data, chunk| async move {
+            data.extend_from_slice(&chunk);
+            Ok(data)
+        })
+        .await
+}
+
+/// Name of the entry an IPFS path points to, the CID itself for a root.
+fn entry_name(path: &str) -> String {
+    let (cid, segments) = split_ipfs_path(path);
+    segments.last().copied().unwrap_or(cid).to_string()
+}
+
+/// Splits an IPFS path into its root CID and the path segments below it.
+fn split_ipfs_path(path: &str) -> (&str, Vec<&str>) {
+    let path = path.strip_prefix("/ipfs/").unwrap_or(path);
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    let cid = segments.next().unwrap_or_default();
+
+    (cid, segments.collect())
+}
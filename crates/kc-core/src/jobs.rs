@@ -0,0 +1,468 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use redis::{AsyncTypedCommands, Direction};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use crate::{
+    app::forget_app,
+    deploy::publish_deployment,
+    models::{
+        app::App,
+        deployment::{Deployment, DeploymentStatus},
+        deployment_node::{DeploymentNode, PinStatus},
+        node::Node,
+    },
+    payloads::{
+        deployment::UpdateDeploymentPayload,
+        deployment_node::UpdateDeploymentNodePayload,
+        node::{NodeDeployPayload, NodeUnpinPayload},
+    },
+    redis::RedisClient,
+    server::ServerState,
+};
+
+const PENDING_KEY: &str = "jobs:pending";
+const PROCESSING_KEY: &str = "jobs:processing";
+const DELAYED_KEY: &str = "jobs:delayed";
+// Longest wait between two attempts, however many attempts are allowed
+const MAX_RETRY_DELAY_SECONDS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct JobsConfig {
+    pub workers: usize,
+    pub max_attempts: u32,
+    pub retry_delay_seconds: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum JobKind {
+    PublishIpns {
+        deployment_id: String,
+    },
+    PinNode {
+        deployment_node_id: String,
+    },
+    PinTimeout {
+        deployment_node_id: String,
+        callback_token: String,
+    },
+    UnpinNode {
+        deployment_node_id: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    pub attempts: u32,
+}
+
+pub async fn enqueue(redis_client: &RedisClient, kind: JobKind) -> Result<(), String> {
+    let job_json = new_job_json(kind)?;
+
+    let mut conn = redis_client
+        .get_multiplexed_tokio_connection()
+        .await
+        .map_err(|e| e.to_string())?;
+    conn.lpush(PENDING_KEY, job_json)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Schedules a job to run once the delay has elapsed.
+pub async fn enqueue_in(
+    redis_client: &RedisClient,
+    kind: JobKind,
+    delay_seconds: u64,
+) -> Result<(), String> {
+    let job_json = new_job_json(kind)?;
+
+    let mut conn = redis_client
+        .get_multiplexed_tokio_connection()
+        .await
+        .map_err(|e| e.to_string())?;
+    conn.zadd(
+        DELAYED_KEY,
+        job_json,
+        Utc::now().timestamp() + delay_seconds as i64,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn new_job_json(kind: JobKind) -> Result<String, String> {
+    let job = Job {
+        id: Uuid::new_v4().to_string(),
+        kind,
+        attempts: 0,
+    };
+    serde_json::to_string(&job).map_err(|e| e.to_string())
+}
+
+/// Requeues jobs left unacknowledged by a previous run, then starts the workers.
+///
+/// Recovery assumes a single satellite instance consumes the queue.
+pub async fn start_workers(state: ServerState) -> Result<(), String> {
+    let mut conn = state
+        .redis_client
+        .get_multiplexed_tokio_connection()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut recovered = 0;
+    while conn
+        .rpoplpush(PROCESSING_KEY, PENDING_KEY)
+        .await
+        .map_err(|e| e.to_string())?
+        .is_some()
+    {
+        recovered += 1;
+    }
+    if recovered > 0 {
+        println!("[Jobs] {} interrupted jobs requeued", recovered);
+    }
+
+    for worker_id in 0..state.server_settings.jobs.workers {
+        let state = state.clone();
+        tokio::spawn(async move { run_worker(worker_id, state).await });
+    }
+
+    Ok(())
+}
+
+async fn run_worker(worker_id: usize, state: ServerState) {
+    loop {
+        // Blocking pops need a connection of their own
+        let mut conn = match state.redis_client.get_multiplexed_tokio_connection().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                eprintln!("[Jobs] Worker {} Redis connection error: {}", worker_id, e);
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+
+        loop {
+            if let Err(e) = promote_delayed_jobs(&mut conn).await {
+                eprintln!("[Jobs] Worker {} delayed jobs error: {}", worker_id, e);
+                break;
+            }
+
+            let job_json = match conn
+                .blmove(
+                    PENDING_KEY,
+                    PROCESSING_KEY,
+                    Direction::Right,
+                    Direction::Left,
+                    1.0,
+                )
+                .await
+            {
+                Ok(Some(job_json)) => job_json,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("[Jobs] Worker {} queue error: {}", worker_id, e);
+                    break;
+                }
+            };
+
+            process_job(&state, &mut conn, &job_json).await;
+
+            // Acknowledge the job once it has been handled, whatever the outcome
+            if let Err(e) = conn.lrem(PROCESSING_KEY, 1, &job_json).await {
+                eprintln!("[Jobs] Worker {} acknowledgement error: {}", worker_id, e);
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+async fn promote_delayed_jobs(
+    conn: &mut redis::aio::MultiplexedConnection,
+) -> Result<(), redis::RedisError> {
+    let due_jobs = conn
+        .zrangebyscore_limit(DELAYED_KEY, "-inf", Utc::now().timestamp(), 0, 100)
+        .await?;
+
+    for job_json in due_jobs {
+        // Only the worker that removed the job gets to requeue it
+        if conn.zrem(DELAYED_KEY, &job_json).await? > 0 {
+            conn.lpush(PENDING_KEY, &job_json).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn process_job(
+    state: &ServerState,
+    conn: &mut redis::aio::MultiplexedConnection,
+    job_json: &str,
+) {
+    let mut job = match serde_json::from_str::<Job>(job_json) {
+        Ok(job) => job,
+        Err(e) => {
+            eprintln!("[Jobs] Dropping malformed job: {}", e);
+            return;
+        }
+    };
+
+    let error = match job.run(state).await {
+        Ok(_) => return,
+        Err(e) => e,
+    };
+
+    job.attempts += 1;
+    let jobs_config = &state.server_settings.jobs;
+    if job.attempts >= jobs_config.max_attempts {
+        eprintln!(
+            "[Jobs] Job {} failed after {} attempts: {}",
+            job.id, job.attempts, error
+        );
+        job.fail(state).await;
+        return;
+    }
+
+    let delay = 2u64
+        .checked_pow(job.attempts - 1)
+        .map_or(u64::MAX, |factor| {
+            jobs_config.retry_delay_seconds.saturating_mul(factor)
+        })
+        .min(MAX_RETRY_DELAY_SECONDS);
+    eprintln!(
+        "[Jobs] Job {} failed (attempt {}), retrying in {}s: {}",
+        job.id, job.attempts, delay, error
+    );
+    let retry_json = match serde_json::to_string(&job) {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("[Jobs] Job {} serialization error: {}", job.id, e);
+            return;
+        }
+    };
+    if let Err(e) = conn
+        .zadd(
+            DELAYED_KEY,
+            retry_json,
+            Utc::now().timestamp() + delay as i64,
+        )
+        .await
+    {
+        eprintln!("[Jobs] Job {} retry scheduling error: {}", job.id, e);
+    }
+}
+
+impl Job {
+    async fn run(&self, state: &ServerState) -> Result<(), String> {
+        match &self.kind {
+            JobKind::PublishIpns { deployment_id } => publish_ipns(state, deployment_id).await,
+            JobKind::PinNode { deployment_node_id } => pin_node(state, deployment_node_id).await,
+            JobKind::PinTimeout {
+                deployment_node_id,
+                callback_token,
+            } => expire_pin(state, deployment_node_id, callback_token).await,
+            JobKind::UnpinNode { deployment_node_id } => {
+                unpin_node(state, deployment_node_id).await
+            }
+        }
+    }
+
+    /// Records the final failure once every attempt has been used.
+    async fn fail(&self, state: &ServerState) {
+        match &self.kind {
+            JobKind::PublishIpns { deployment_id } => {
+                let _ = Deployment::update_by_id(
+                    &state.db_pool,
+                    deployment_id,
+                    &UpdateDeploymentPayload {
+                        app_id: None,
+                        cid: None,
+                        status: Some(DeploymentStatus::FAILED),
+                    },
+                )
+                .await;
+            }
+            JobKind::PinNode { deployment_node_id } => {
+                let _ = DeploymentNode::update_by_id(
+                    &state.db_pool,
+                    deployment_node_id,
+                    &UpdateDeploymentNodePayload {
+                        deployment_id: None,
+                        node_id: None,
+                        status: Some(PinStatus::FAILED),
+                    },
+                )
+                .await;
+            }
+            // The content stays on the node, nothing else refers to it anymore
+            JobKind::PinTimeout { .. } | JobKind::UnpinNode { .. } => {}
+        }
+    }
+}
+
+async fn publish_ipns(state: &ServerState, deployment_id: &String) -> Result<(), String> {
+    let deployment = Deployment::find_by_id(&state.db_pool, deployment_id).await?;
+    if !matches!(deployment.status, DeploymentStatus::PUBLISHING) {
+        return Ok(());
+    }
+
+    let app = App::find_by_id(&state.db_pool, &deployment.app_id.to_string()).await?;
+    let key_name = match &app.key_name {
+        Some(key_name) => key_name.clone(),
+        None => return Err(format!("App {} has no IPNS key", app.name)),
+    };
+
+    publish_deployment(
+        &state.db_pool,
+        state.ipfs.as_ref(),
+        &app,
+        &key_name,
+        &deployment,
+    )
+    .await?;
+    forget_app(&state.app_registry, &app.name);
+
+    Ok(())
+}
+
+async fn pin_node(state: &ServerState, deployment_node_id: &String) -> Result<(), String> {
+    let deployment_node = DeploymentNode::find_by_id(&state.db_pool, deployment_node_id).await?;
+    // Already requested: the node now owes us its confirmation callback
+    if !matches!(deployment_node.status, PinStatus::PINNING)
+        || deployment_node.requested_at.is_some()
+    {
+        return Ok(());
+    }
+    let callback_token = match &deployment_node.callback_token {
+        Some(token) => token.clone(),
+        None => return Err(format!("Pin {} has no callback token", deployment_node.id)),
+    };
+
+    let deployment =
+        Deployment::find_by_id(&state.db_pool, &deployment_node.deployment_id.to_string()).await?;
+    let app = App::find_by_id(&state.db_pool, &deployment.app_id.to_string()).await?;
+    let node = Node::find_by_id(&state.db_pool, &deployment_node.node_id.to_string()).await?;
+
+    // Requested and timed from before the request, the callback may come back right away
+    let Some(deployment_node) = deployment_node.mark_requested(&state.db_pool).await? else {
+        return Ok(());
+    };
+    enqueue_in(
+        &state.redis_client,
+        JobKind::PinTimeout {
+            deployment_node_id: deployment_node.id.to_string(),
+            callback_token: callback_token.clone(),
+        },
+        state.server_settings.deploy.pin_timeout_seconds,
+    )
+    .await?;
+
+    let payload = NodeDeployPayload {
+        name: app.name.clone(),
+        cid: deployment.cid.clone(),
+        deployment_node_id: deployment_node.id.to_string(),
+        callback_token,
+    };
+    if let Err(e) = send_pin_request(&node, &payload).await {
+        // The retry sends it again, still within the timeout of the first attempt
+        deployment_node.clear_requested(&state.db_pool).await?;
+        return Err(e);
+    }
+
+    println!("[Jobs] Send app deployment to node: id={}", node.id);
+    Ok(())
+}
+
+async fn send_pin_request(node: &Node, payload: &NodeDeployPayload) -> Result<(), String> {
+    let deploy_url = format!("http://{}:{}/api/deploy", node.ip, node.port);
+    let response = Client::new()
+        .post(&deploy_url)
+        .json(payload)
+        .send()
+        .await
+        .map_err(|e| format!("Error sending app deployment to node {}: {}", node.id, e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Node {} refused app deployment: status={}",
+            node.id,
+            response.status()
+        ));
+    }
+
+    Ok(())
+}
+
+async fn expire_pin(
+    state: &ServerState,
+    deployment_node_id: &str,
+    callback_token: &str,
+) -> Result<(), String> {
+    if let Some(deployment_node) =
+        DeploymentNode::expire(&state.db_pool, deployment_node_id, callback_token).await?
+    {
+        eprintln!(
+            "[Jobs] Node {} did not confirm pin {} in time",
+            deployment_node.node_id, deployment_node.id
+        );
+    }
+
+    Ok(())
+}
+
+async fn unpin_node(state: &ServerState, deployment_node_id: &String) -> Result<(), String> {
+    let deployment_node = DeploymentNode::find_by_id(&state.db_pool, deployment_node_id).await?;
+    if matches!(deployment_node.status, PinStatus::UNPINNED) {
+        return Ok(());
+    }
+
+    let deployment =
+        Deployment::find_by_id(&state.db_pool, &deployment_node.deployment_id.to_string()).await?;
+    let node = Node::find_by_id(&state.db_pool, &deployment_node.node_id.to_string()).await?;
+
+    let unpin_url = format!("http://{}:{}/api/unpin", node.ip, node.port);
+    let response = Client::new()
+        .post(&unpin_url)
+        .json(&NodeUnpinPayload {
+            cid: deployment.cid.clone(),
+            deployment_node_id: deployment_node.id.to_string(),
+        })
+        .send()
+        .await
+        .map_err(|e| format!("Error sending unpin request to node {}: {}", node.id, e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Node {} refused unpin request: status={}",
+            node.id,
+            response.status()
+        ));
+    }
+
+    println!(
+        "[Jobs] Node {} unpinned CID {} of deployment {}",
+        node.id, deployment.cid, deployment.id
+    );
+    deployment_node
+        .update(
+            &state.db_pool,
+            &UpdateDeploymentNodePayload {
+                deployment_id: None,
+                node_id: None,
+                status: Some(PinStatus::UNPINNED),
+            },
+        )
+        .await?;
+
+    Ok(())
+}
@@ -1,10 +1,22 @@
+pub mod access;
 pub mod app;
 pub mod authentication;
 pub mod database;
+pub mod deploy;
+pub mod domain;
+pub mod events;
+pub mod gateway;
+pub mod ipfs;
+pub mod jobs;
 pub mod json;
 pub mod models;
 pub mod node;
 pub mod payloads;
 pub mod redis;
+pub mod replication;
+pub mod reputation;
+pub mod retention;
+pub mod selection;
 pub mod server;
+pub mod site_rules;
 pub mod utils;
@@ -1,16 +1,62 @@
 use async_graphql::{Context, Object};
 use chrono::{DateTime, Utc};
-use serde::ser::{Serialize, SerializeStruct};
-use sqlx::{QueryBuilder, prelude::FromRow, types::Uuid};
+use serde::{
+    Deserialize,
+    ser::{Serialize, SerializeStruct},
+};
+use sqlx::{
+    QueryBuilder,
+    prelude::{FromRow, Type},
+    types::Uuid,
+};
 use struct_iterable::Iterable;
 
 use crate::{
+    authentication::Claims,
     database::DbPool,
     models::{deployment::Deployment, node::Node, team::Team},
     payloads::app::{CreateAppPayload, UpdateAppPayload},
     server::ServerState,
 };
 
+/// Who the gateway serves an app to.
+#[derive(Debug, Type, serde::Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "access_mode")]
+pub enum AccessMode {
+    #[sqlx(rename = "PUBLIC")]
+    PUBLIC,
+    #[sqlx(rename = "PASSWORD")]
+    PASSWORD,
+    #[sqlx(rename = "TOKEN")]
+    TOKEN,
+    #[sqlx(rename = "TEAM")]
+    TEAM,
+}
+
+impl AccessMode {
+    pub fn from_name(name: &str) -> Result<AccessMode, String> {
+        match name.to_ascii_uppercase().as_str() {
+            "PUBLIC" => Ok(AccessMode::PUBLIC),
+            "PASSWORD" => Ok(AccessMode::PASSWORD),
+            "TOKEN" => Ok(AccessMode::TOKEN),
+            "TEAM" => Ok(AccessMode::TEAM),
+            _ => Err(format!(
+                "Unknown access mode {}, expected PUBLIC, PASSWORD, TOKEN or TEAM",
+                name
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccessMode::PUBLIC => "PUBLIC",
+            AccessMode::PASSWORD => "PASSWORD",
+            AccessMode::TOKEN => "TOKEN",
+            AccessMode::TEAM => "TEAM",
+        }
+    }
+}
+
 #[derive(FromRow, Debug, Clone)]
 pub struct App {
     pub id: Uuid,
@@ -18,6 +64,13 @@ pub struct App {
     pub name: String,
     pub key_name: Option<String>,
     pub ipns_name: Option<String>,
+    pub replication_factor: i32,
+    pub retention_count: Option<i32>,
+    pub retention_days: Option<i32>,
+    pub access_mode: AccessMode,
+    pub access_password_hash: Option<String>,
+    /// Bumped on every access change, sessions and links carry the version they were issued for
+    pub access_version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -33,6 +86,10 @@ impl Serialize for App {
         state.serialize_field("name", &self.name)?;
         state.serialize_field("key_name", &self.key_name)?;
         state.serialize_field("ipns_name", &self.ipns_name)?;
+        state.serialize_field("replication_factor", &self.replication_factor)?;
+        state.serialize_field("retention_count", &self.retention_count)?;
+        state.serialize_field("retention_days", &self.retention_days)?;
+        state.serialize_field("access_mode", &self.access_mode)?;
         state.serialize_field("created_at", &self.created_at.to_string())?;
         state.serialize_field("updated_at", &self.updated_at.to_string())?;
         state.end()
@@ -86,6 +143,21 @@ impl App {
         }
     }
 
+    /// Tells a missing app, `None`, from a failing database, `Err`.
+    pub async fn find_optional_by_name(
+        db_pool: &DbPool,
+        name: &str,
+    ) -> Result<Option<App>, String> {
+        match sqlx::query_as::<_, App>("SELECT * FROM apps WHERE name = $1")
+            .bind(name)
+            .fetch_optional(db_pool)
+            .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     pub async fn find_by_user_id(db_pool: &DbPool, id: &String) -> Result<Vec<App>, String> {
         match Uuid::parse_str(id) {
             Ok(uuid) => {
@@ -102,6 +174,67 @@ impl App {
         }
     }
 
+    /// Admins and members of the owning team can manage an app.
+    pub async fn is_managed_by(&self, db_pool: &DbPool, claims: &Claims) -> Result<bool, String> {
+        Team::is_managed_by(db_pool, &self.team_id, claims).await
+    }
+
+    /// Replaces the retention policy, `None` keeping deployments regardless of that criterion.
+    pub async fn set_retention(
+        &self,
+        db_pool: &DbPool,
+        retention_count: Option<i32>,
+        retention_days: Option<i32>,
+    ) -> Result<App, String> {
+        match sqlx::query_as::<_, App>(
+            "UPDATE apps SET retention_count = $1, retention_days = $2 WHERE id = $3 RETURNING *",
+        )
+        .bind(retention_count)
+        .bind(retention_days)
+        .bind(self.id)
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Replaces the access mode, the password hash only being kept in PASSWORD mode.
+    ///
+    /// Sessions and signed links issued before are revoked, even if the mode is unchanged.
+    pub async fn set_access(
+        &self,
+        db_pool: &DbPool,
+        access_mode: AccessMode,
+        access_password_hash: Option<String>,
+    ) -> Result<App, String> {
+        match sqlx::query_as::<_, App>(
+            "UPDATE apps SET access_mode = $1, access_password_hash = $2, access_version = access_version + 1 WHERE id = $3 RETURNING *",
+        )
+        .bind(access_mode)
+        .bind(access_password_hash)
+        .bind(self.id)
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub async fn find_with_retention(db_pool: &DbPool) -> Result<Vec<App>, String> {
+        match sqlx::query_as::<_, App>(
+            "SELECT * FROM apps WHERE retention_count IS NOT NULL OR retention_days IS NOT NULL",
+        )
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     pub async fn update_by_id(
         db_pool: &DbPool,
         id: &String,
@@ -158,6 +291,16 @@ async fn app_update_by_id(
                         i += 1;
                     }
                 }
+                if let Some(Some(v)) = field_value.downcast_ref::<Option<i32>>() {
+                    if i == 0 {
+                        query_builder.push(" SET ");
+                    } else {
+                        query_builder.push(", ");
+                    }
+
+                    query_builder.push(name).push(" = ").push_bind(v);
+                    i += 1;
+                }
             }
 
             query_builder.push(" WHERE id = ").push_bind(uuid);
@@ -194,6 +337,18 @@ impl App {
             None => "",
         }
     }
+    async fn replication_factor(&self) -> i32 {
+        self.replication_factor
+    }
+    async fn retention_count(&self) -> Option<i32> {
+        self.retention_count
+    }
+    async fn retention_days(&self) -> Option<i32> {
+        self.retention_days
+    }
+    async fn access_mode(&self) -> &str {
+        self.access_mode.name()
+    }
     async fn created_at(&self) -> DateTime<Utc> {
         self.created_at
     }
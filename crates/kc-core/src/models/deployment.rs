@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize, ser::SerializeStruct};
 use sqlx::{
     QueryBuilder,
     prelude::{FromRow, Type},
-    types::Uuid,
+    types::{Json, Uuid},
 };
 use struct_iterable::Iterable;
 
@@ -13,6 +13,7 @@ use crate::{
     models::{app::App, node::Node},
     payloads::deployment::{CreateDeploymentPayload, UpdateDeploymentPayload},
     server::ServerState,
+    site_rules::SiteRules,
 };
 
 #[derive(Debug, Type, Serialize, Deserialize, Clone, Copy)]
@@ -26,6 +27,12 @@ pub enum DeploymentStatus {
     DEPLOYED,
     #[sqlx(rename = "FAILED")]
     FAILED,
+    #[sqlx(rename = "SUPERSEDED")]
+    SUPERSEDED,
+    #[sqlx(rename = "ARCHIVED")]
+    ARCHIVED,
+    #[sqlx(rename = "PREVIEW")]
+    PREVIEW,
 }
 
 #[derive(FromRow, Debug, Clone)]
@@ -34,6 +41,10 @@ pub struct Deployment {
     pub app_id: Uuid,
     pub cid: String,
     pub status: DeploymentStatus,
+    pub file_count: i64,
+    pub total_size: i64,
+    pub source_deployment_id: Option<Uuid>,
+    pub site_rules: Option<Json<SiteRules>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -47,31 +58,65 @@ impl Serialize for Deployment {
         state.serialize_field("app_id", &self.app_id.to_string())?;
         state.serialize_field("cid", &self.cid)?;
         state.serialize_field("status", &self.status)?;
+        state.serialize_field("file_count", &self.file_count)?;
+        state.serialize_field("total_size", &self.total_size)?;
+        state.serialize_field(
+            "source_deployment_id",
+            &self.source_deployment_id.map(|id| id.to_string()),
+        )?;
+        state.serialize_field(
+            "site_rules",
+            &self.site_rules.as_ref().map(|rules| &rules.0),
+        )?;
         state.serialize_field("created_at", &self.created_at.to_string())?;
         state.end()
     }
 }
 
 impl Deployment {
+    /// Content of pending, failed and archived deployments is not kept on the satellite.
+    pub fn is_servable(&self) -> bool {
+        !matches!(
+            self.status,
+            DeploymentStatus::PENDING | DeploymentStatus::FAILED | DeploymentStatus::ARCHIVED
+        )
+    }
+
     pub async fn create(
         db_pool: &DbPool,
         payload: &CreateDeploymentPayload,
     ) -> Result<Deployment, String> {
-        match Uuid::parse_str(&payload.app_id) {
-            Ok(app_id) => {
-                match sqlx::query_as::<_, Deployment>(
-                    "INSERT INTO deployments (app_id, cid) VALUES ($1, $2) RETURNING *",
-                )
-                .bind(app_id)
-                .bind(payload.cid.clone())
-                .fetch_one(db_pool)
-                .await
-                {
-                    Ok(result) => Ok(result),
-                    Err(e) => Err(e.to_string()),
+        let app_id = match Uuid::parse_str(&payload.app_id) {
+            Ok(app_id) => app_id,
+            Err(e) => return Err(format!("Invalid UUID format for app_id: {}", e)),
+        };
+        let source_deployment_id = match &payload.source_deployment_id {
+            Some(id) => match Uuid::parse_str(id) {
+                Ok(uuid) => Some(uuid),
+                Err(e) => {
+                    return Err(format!(
+                        "Invalid UUID format for source_deployment_id: {}",
+                        e
+                    ));
                 }
-            }
-            Err(e) => Err(format!("Invalid UUID format for app_id: {}", e)),
+            },
+            None => None,
+        };
+
+        match sqlx::query_as::<_, Deployment>(
+            "INSERT INTO deployments (app_id, cid, file_count, total_size, source_deployment_id, site_rules) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *",
+        )
+        .bind(app_id)
+        .bind(payload.cid.clone())
+        .bind(payload.file_count)
+        .bind(payload.total_size)
+        .bind(source_deployment_id)
+        .bind(payload.site_rules.clone().map(Json))
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
         }
     }
 
@@ -107,6 +152,117 @@ impl Deployment {
         deployment_update_by_id(db_pool, &self.id.to_string(), payload).await
     }
 
+    pub async fn find_by_app_id(
+        db_pool: &DbPool,
+        app_id: &Uuid,
+    ) -> Result<Vec<Deployment>, String> {
+        match sqlx::query_as::<_, Deployment>(
+            "SELECT * FROM deployments WHERE app_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(app_id)
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Returns the latest deployment of a CID whose content is still kept, if any.
+    pub async fn find_servable_by_cid(
+        db_pool: &DbPool,
+        cid: &str,
+    ) -> Result<Option<Deployment>, String> {
+        match sqlx::query_as::<_, Deployment>(
+            "SELECT * FROM deployments WHERE cid = $1 AND status NOT IN ('PENDING', 'FAILED', 'ARCHIVED') ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(cid)
+        .fetch_optional(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Returns the deployment the app currently serves, if any, unique per app.
+    pub async fn find_live_by_app_id(
+        db_pool: &DbPool,
+        app_id: &Uuid,
+    ) -> Result<Option<Deployment>, String> {
+        match sqlx::query_as::<_, Deployment>(
+            "SELECT * FROM deployments WHERE app_id = $1 AND status = 'DEPLOYED'",
+        )
+        .bind(app_id)
+        .fetch_optional(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub async fn find_by_status(
+        db_pool: &DbPool,
+        status: DeploymentStatus,
+    ) -> Result<Vec<Deployment>, String> {
+        match sqlx::query_as::<_, Deployment>("SELECT * FROM deployments WHERE status = $1")
+            .bind(status)
+            .fetch_all(db_pool)
+            .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Tells whether another deployment still needing its content shares this CID.
+    pub async fn is_cid_shared(&self, db_pool: &DbPool) -> Result<bool, String> {
+        match sqlx::query(
+            "SELECT 1 FROM deployments WHERE cid = $1 AND id <> $2 AND status NOT IN ('ARCHIVED', 'FAILED') LIMIT 1",
+        )
+        .bind(&self.cid)
+        .bind(self.id)
+        .fetch_optional(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result.is_some()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Marks this deployment as the live one of its app, superseding the previous one.
+    pub async fn mark_deployed(&self, db_pool: &DbPool) -> Result<Deployment, String> {
+        let mut transaction = db_pool.begin().await.map_err(|e| e.to_string())?;
+
+        // Concurrent deployments of the app wait here instead of colliding on the live index
+        sqlx::query("SELECT 1 FROM apps WHERE id = $1 FOR UPDATE")
+            .bind(self.app_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        sqlx::query(
+            "UPDATE deployments SET status = 'SUPERSEDED' WHERE app_id = $1 AND status = 'DEPLOYED' AND id <> $2",
+        )
+        .bind(self.app_id)
+        .bind(self.id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let deployment = sqlx::query_as::<_, Deployment>(
+            "UPDATE deployments SET status = 'DEPLOYED' WHERE id = $1 RETURNING *",
+        )
+        .bind(self.id)
+        .fetch_one(&mut *transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        transaction.commit().await.map_err(|e| e.to_string())?;
+        Ok(deployment)
+    }
+
     pub async fn delete_by_id(db_pool: &DbPool, id: &String) -> Result<Deployment, String> {
         match Uuid::parse_str(id) {
             Ok(uuid) => {
@@ -192,8 +348,20 @@ impl Deployment {
             DeploymentStatus::PUBLISHING => "PUBLISHING",
             DeploymentStatus::DEPLOYED => "DEPLOYED",
             DeploymentStatus::FAILED => "FAILED",
+            DeploymentStatus::SUPERSEDED => "SUPERSEDED",
+            DeploymentStatus::ARCHIVED => "ARCHIVED",
+            DeploymentStatus::PREVIEW => "PREVIEW",
         }
     }
+    async fn file_count(&self) -> i64 {
+        self.file_count
+    }
+    async fn total_size(&self) -> i64 {
+        self.total_size
+    }
+    async fn source_deployment_id(&self) -> Option<Uuid> {
+        self.source_deployment_id
+    }
     async fn created_at(&self) -> DateTime<Utc> {
         self.created_at
     }
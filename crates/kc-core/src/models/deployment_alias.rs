@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use serde::ser::{Serialize, SerializeStruct};
+use sqlx::{prelude::FromRow, types::Uuid};
+
+use crate::{database::DbPool, payloads::deployment_alias::CreateDeploymentAliasPayload};
+
+#[derive(FromRow, Debug, Clone)]
+pub struct DeploymentAlias {
+    pub id: Uuid,
+    pub app_id: Uuid,
+    pub deployment_id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Serialize for DeploymentAlias {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("DeploymentAlias", 6)?;
+        state.serialize_field("id", &self.id.to_string())?;
+        state.serialize_field("app_id", &self.app_id.to_string())?;
+        state.serialize_field("deployment_id", &self.deployment_id.to_string())?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("created_at", &self.created_at.to_string())?;
+        state.serialize_field("updated_at", &self.updated_at.to_string())?;
+        state.end()
+    }
+}
+
+impl DeploymentAlias {
+    /// Points the alias to the deployment, moving it if the app already uses the name.
+    pub async fn upsert(
+        db_pool: &DbPool,
+        payload: &CreateDeploymentAliasPayload,
+    ) -> Result<DeploymentAlias, String> {
+        let app_id = match Uuid::parse_str(&payload.app_id) {
+            Ok(app_id) => app_id,
+            Err(e) => return Err(format!("Invalid UUID format for app_id: {}", e)),
+        };
+        let deployment_id = match Uuid::parse_str(&payload.deployment_id) {
+            Ok(deployment_id) => deployment_id,
+            Err(e) => return Err(format!("Invalid UUID format for deployment_id: {}", e)),
+        };
+
+        match sqlx::query_as::<_, DeploymentAlias>(
+            "INSERT INTO deployment_aliases (app_id, deployment_id, name) VALUES ($1, $2, $3) ON CONFLICT (app_id, name) DO UPDATE SET deployment_id = EXCLUDED.deployment_id RETURNING *",
+        )
+        .bind(app_id)
+        .bind(deployment_id)
+        .bind(payload.name.clone())
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub async fn find_by_app_id(
+        db_pool: &DbPool,
+        app_id: &Uuid,
+    ) -> Result<Vec<DeploymentAlias>, String> {
+        match sqlx::query_as::<_, DeploymentAlias>(
+            "SELECT * FROM deployment_aliases WHERE app_id = $1 ORDER BY name ASC",
+        )
+        .bind(app_id)
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub async fn find_by_name(
+        db_pool: &DbPool,
+        app_id: &Uuid,
+        name: &str,
+    ) -> Result<Option<DeploymentAlias>, String> {
+        match sqlx::query_as::<_, DeploymentAlias>(
+            "SELECT * FROM deployment_aliases WHERE app_id = $1 AND name = $2",
+        )
+        .bind(app_id)
+        .bind(name)
+        .fetch_optional(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub async fn delete(&self, db_pool: &DbPool) -> Result<DeploymentAlias, String> {
+        match sqlx::query_as::<_, DeploymentAlias>(
+            "DELETE FROM deployment_aliases WHERE id = $1 RETURNING *",
+        )
+        .bind(self.id)
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
@@ -21,6 +21,10 @@ pub enum PinStatus {
     PINNED,
     #[sqlx(rename = "FAILED")]
     FAILED,
+    #[sqlx(rename = "LOST")]
+    LOST,
+    #[sqlx(rename = "UNPINNED")]
+    UNPINNED,
 }
 
 #[derive(FromRow, Debug)]
@@ -29,6 +33,9 @@ pub struct DeploymentNode {
     pub deployment_id: Uuid,
     pub node_id: Uuid,
     pub status: PinStatus,
+    pub callback_token: Option<String>,
+    pub requested_at: Option<DateTime<Utc>>,
+    pub confirmed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -38,11 +45,19 @@ impl Serialize for DeploymentNode {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("DeploymentNode", 5)?;
+        let mut state = serializer.serialize_struct("DeploymentNode", 8)?;
         state.serialize_field("id", &self.id.to_string())?;
         state.serialize_field("deployment_id", &self.deployment_id.to_string())?;
         state.serialize_field("node_id", &self.node_id.to_string())?;
         state.serialize_field("status", &self.status)?;
+        state.serialize_field(
+            "requested_at",
+            &self.requested_at.map(|date| date.to_string()),
+        )?;
+        state.serialize_field(
+            "confirmed_at",
+            &self.confirmed_at.map(|date| date.to_string()),
+        )?;
         state.serialize_field("created_at", &self.created_at.to_string())?;
         state.serialize_field("updated_at", &self.updated_at.to_string())?;
         state.end()
@@ -58,10 +73,11 @@ impl DeploymentNode {
             Ok(deployment_uuid) => match Uuid::parse_str(&payload.node_id) {
                 Ok(node_uuid) => {
                     return match sqlx::query_as::<_, DeploymentNode>(
-                        "INSERT INTO deployments_nodes (deployment_id, node_id) VALUES ($1, $2) RETURNING *",
+                        "INSERT INTO deployments_nodes (deployment_id, node_id, callback_token) VALUES ($1, $2, $3) RETURNING *",
                     )
                     .bind(deployment_uuid)
                     .bind(node_uuid)
+                    .bind(generate_callback_token())
                     .fetch_one(db_pool)
                     .await
                     {
@@ -109,6 +125,150 @@ impl DeploymentNode {
         deployment_node_update_by_id(db_pool, &self.id.to_string(), payload).await
     }
 
+    /// Records that the pin is being requested from the node, before the request is sent so
+    /// that its callback always comes after. Returns `None` if it was already requested.
+    pub async fn mark_requested(&self, db_pool: &DbPool) -> Result<Option<DeploymentNode>, String> {
+        match sqlx::query_as::<_, DeploymentNode>(
+            "UPDATE deployments_nodes SET requested_at = NOW() WHERE id = $1 AND status = 'PINNING' AND requested_at IS NULL RETURNING *",
+        )
+        .bind(self.id)
+        .fetch_optional(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Lets the pin be requested again after the node could not be reached.
+    pub async fn clear_requested(&self, db_pool: &DbPool) -> Result<(), String> {
+        match sqlx::query(
+            "UPDATE deployments_nodes SET requested_at = NULL WHERE id = $1 AND status = 'PINNING'",
+        )
+        .bind(self.id)
+        .execute(db_pool)
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Stores the pin outcome reported by the node, returns `None` if the pin is no longer
+    /// pending, having timed out, been lost or released meanwhile.
+    pub async fn confirm(
+        &self,
+        db_pool: &DbPool,
+        status: PinStatus,
+    ) -> Result<Option<DeploymentNode>, String> {
+        match sqlx::query_as::<_, DeploymentNode>(
+            "UPDATE deployments_nodes SET status = $1, confirmed_at = NOW() WHERE id = $2 AND status = 'PINNING' RETURNING *",
+        )
+        .bind(status)
+        .bind(self.id)
+        .fetch_optional(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Pins still held for a deployment and for the archived deployments sharing its CID,
+    /// which were kept while the CID was in use.
+    pub async fn find_unreleased_by_cid(
+        db_pool: &DbPool,
+        deployment_id: &Uuid,
+        cid: &str,
+    ) -> Result<Vec<DeploymentNode>, String> {
+        match sqlx::query_as::<_, DeploymentNode>(
+            "SELECT dn.* FROM deployments_nodes dn JOIN deployments d ON d.id = dn.deployment_id WHERE d.cid = $1 AND (d.id = $2 OR d.status = 'ARCHIVED') AND dn.status <> 'UNPINNED' ORDER BY dn.created_at",
+        )
+        .bind(cid)
+        .bind(deployment_id)
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub async fn find_by_deployment_id(
+        db_pool: &DbPool,
+        deployment_id: &Uuid,
+    ) -> Result<Vec<DeploymentNode>, String> {
+        match sqlx::query_as::<_, DeploymentNode>(
+            "SELECT * FROM deployments_nodes WHERE deployment_id = $1 ORDER BY created_at",
+        )
+        .bind(deployment_id)
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Pins of a node whose status changed since the given date.
+    pub async fn find_by_node_id_since(
+        db_pool: &DbPool,
+        node_id: &Uuid,
+        since: &DateTime<Utc>,
+    ) -> Result<Vec<DeploymentNode>, String> {
+        match sqlx::query_as::<_, DeploymentNode>(
+            "SELECT * FROM deployments_nodes WHERE node_id = $1 AND updated_at >= $2 ORDER BY created_at",
+        )
+        .bind(node_id)
+        .bind(since)
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Starts a new pin cycle on the same node, with a fresh callback token.
+    pub async fn restart(&self, db_pool: &DbPool) -> Result<DeploymentNode, String> {
+        match sqlx::query_as::<_, DeploymentNode>(
+            "UPDATE deployments_nodes SET status = 'PINNING', callback_token = $1, requested_at = NULL, confirmed_at = NULL WHERE id = $2 RETURNING *",
+        )
+        .bind(generate_callback_token())
+        .bind(self.id)
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Marks the pin as failed if the node never confirmed the cycle of this callback token,
+    /// returns `None` otherwise, including when the pin was restarted since.
+    pub async fn expire(
+        db_pool: &DbPool,
+        id: &str,
+        callback_token: &str,
+    ) -> Result<Option<DeploymentNode>, String> {
+        match Uuid::parse_str(id) {
+            Ok(uuid) => {
+                match sqlx::query_as::<_, DeploymentNode>(
+                    "UPDATE deployments_nodes SET status = 'FAILED' WHERE id = $1 AND status = 'PINNING' AND callback_token = $2 RETURNING *",
+                )
+                .bind(uuid)
+                .bind(callback_token)
+                .fetch_optional(db_pool)
+                .await
+                {
+                    Ok(result) => Ok(result),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Err(e) => Err(format!("Invalid UUID format: {}", e)),
+        }
+    }
+
     pub async fn delete_by_id(db_pool: &DbPool, id: &String) -> Result<DeploymentNode, String> {
         match Uuid::parse_str(id) {
             Ok(uuid) => {
@@ -128,6 +288,10 @@ impl DeploymentNode {
     }
 }
 
+fn generate_callback_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
 async fn deployment_node_update_by_id(
     db_pool: &DbPool,
     id: &String,
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::ser::{Serialize, SerializeStruct};
+use sqlx::{prelude::FromRow, types::Uuid};
+
+use crate::{database::DbPool, payloads::domain::CreateDomainPayload};
+
+#[derive(FromRow, Debug, Clone)]
+pub struct Domain {
+    pub id: Uuid,
+    pub app_id: Uuid,
+    pub hostname: String,
+    pub verification_token: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Serialize for Domain {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Domain", 7)?;
+        state.serialize_field("id", &self.id.to_string())?;
+        state.serialize_field("app_id", &self.app_id.to_string())?;
+        state.serialize_field("hostname", &self.hostname)?;
+        state.serialize_field("verification_token", &self.verification_token)?;
+        state.serialize_field(
+            "verified_at",
+            &self.verified_at.map(|date| date.to_string()),
+        )?;
+        state.serialize_field("created_at", &self.created_at.to_string())?;
+        state.serialize_field("updated_at", &self.updated_at.to_string())?;
+        state.end()
+    }
+}
+
+impl Domain {
+    pub async fn create(db_pool: &DbPool, payload: &CreateDomainPayload) -> Result<Domain, String> {
+        match Uuid::parse_str(&payload.app_id) {
+            Ok(app_id) => {
+                match sqlx::query_as::<_, Domain>(
+                    "INSERT INTO domains (app_id, hostname, verification_token) VALUES ($1, $2, $3) RETURNING *",
+                )
+                .bind(app_id)
+                .bind(payload.hostname.clone())
+                .bind(Uuid::new_v4().simple().to_string())
+                .fetch_one(db_pool)
+                .await
+                {
+                    Ok(result) => Ok(result),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Err(e) => Err(format!("Invalid UUID format for app_id: {}", e)),
+        }
+    }
+
+    pub async fn find_by_id(db_pool: &DbPool, id: &str) -> Result<Domain, String> {
+        match Uuid::parse_str(id) {
+            Ok(uuid) => {
+                match sqlx::query_as::<_, Domain>("SELECT * FROM domains WHERE id = $1")
+                    .bind(uuid)
+                    .fetch_one(db_pool)
+                    .await
+                {
+                    Ok(result) => Ok(result),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Err(e) => Err(format!("Invalid UUID format: {}", e)),
+        }
+    }
+
+    pub async fn find_by_app_id(db_pool: &DbPool, app_id: &Uuid) -> Result<Vec<Domain>, String> {
+        match sqlx::query_as::<_, Domain>(
+            "SELECT * FROM domains WHERE app_id = $1 ORDER BY created_at ASC",
+        )
+        .bind(app_id)
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Returns the claim of a hostname whose ownership is verified, unverified claims reserving
+    /// nothing.
+    pub async fn find_verified_by_hostname(
+        db_pool: &DbPool,
+        hostname: &str,
+    ) -> Result<Option<Domain>, String> {
+        match sqlx::query_as::<_, Domain>(
+            "SELECT * FROM domains WHERE hostname = $1 AND verified_at IS NOT NULL",
+        )
+        .bind(hostname)
+        .fetch_optional(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Returns the name of the app served on a hostname, once its ownership is verified.
+    pub async fn find_app_name_by_hostname(
+        db_pool: &DbPool,
+        hostname: &str,
+    ) -> Result<Option<String>, String> {
+        match sqlx::query_scalar::<_, String>(
+            "SELECT apps.name FROM domains JOIN apps ON domains.app_id = apps.id WHERE domains.hostname = $1 AND domains.verified_at IS NOT NULL",
+        )
+        .bind(hostname)
+        .fetch_optional(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub async fn mark_verified(&self, db_pool: &DbPool) -> Result<Domain, String> {
+        match sqlx::query_as::<_, Domain>(
+            "UPDATE domains SET verified_at = NOW() WHERE id = $1 RETURNING *",
+        )
+        .bind(self.id)
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub async fn delete_by_id(db_pool: &DbPool, id: &str) -> Result<Domain, String> {
+        match Uuid::parse_str(id) {
+            Ok(uuid) => {
+                match sqlx::query_as::<_, Domain>("DELETE FROM domains WHERE id = $1 RETURNING *")
+                    .bind(uuid)
+                    .fetch_one(db_pool)
+                    .await
+                {
+                    Ok(result) => Ok(result),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Err(e) => Err(format!("Invalid UUID format: {}", e)),
+        }
+    }
+}
@@ -1,7 +1,12 @@
 pub mod app;
 pub mod deployment;
+pub mod deployment_alias;
 pub mod deployment_node;
+pub mod domain;
+pub mod mutation;
 pub mod node;
+pub mod node_reputation;
+pub mod node_status_change;
 pub mod query;
 pub mod team;
 pub mod user;
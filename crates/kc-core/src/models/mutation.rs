@@ -0,0 +1,226 @@
+use async_graphql::{Context, Object};
+
+use crate::{
+    access::{
+        AccessScope, AppAccess, TEAM_SIGN_IN_TTL_SECONDS, issue_access_token, validate_access,
+    },
+    app::forget_app,
+    authentication::Claims,
+    deploy::{promote_deployment, rollback_app},
+    models::{
+        app::{AccessMode, App},
+        deployment::Deployment,
+        team::Team,
+    },
+    payloads::app::UpdateAppPayload,
+    replication::validate_replication_factor,
+    retention::validate_retention,
+    selection::validate_region,
+    server::ServerState,
+    utils::auth::hash_password,
+};
+
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+    async fn rollback_app(
+        &self,
+        ctx: &Context<'_>,
+        app_id: String,
+        deployment_id: String,
+    ) -> Result<Deployment, String> {
+        let (state, app) = managed_app(ctx, &app_id).await?;
+
+        let source = Deployment::find_by_id(&state.db_pool, &deployment_id).await?;
+        rollback_app(state, &app, &source).await
+    }
+
+    async fn promote_deployment(
+        &self,
+        ctx: &Context<'_>,
+        app_id: String,
+        deployment_id: String,
+    ) -> Result<Deployment, String> {
+        let (state, app) = managed_app(ctx, &app_id).await?;
+
+        let preview = Deployment::find_by_id(&state.db_pool, &deployment_id).await?;
+        promote_deployment(state, &app, &preview).await
+    }
+
+    async fn set_replication_factor(
+        &self,
+        ctx: &Context<'_>,
+        app_id: String,
+        replication_factor: i32,
+    ) -> Result<App, String> {
+        let (state, app) = managed_app(ctx, &app_id).await?;
+
+        validate_replication_factor(&state.server_settings.replication, replication_factor)?;
+        app.update(
+            &state.db_pool,
+            &UpdateAppPayload {
+                team_id: None,
+                name: None,
+                key_name: None,
+                ipns_name: None,
+                replication_factor: Some(replication_factor),
+            },
+        )
+        .await
+    }
+
+    async fn set_retention_policy(
+        &self,
+        ctx: &Context<'_>,
+        app_id: String,
+        retention_count: Option<i32>,
+        retention_days: Option<i32>,
+    ) -> Result<App, String> {
+        let (state, app) = managed_app(ctx, &app_id).await?;
+
+        validate_retention(retention_count, retention_days)?;
+        app.set_retention(&state.db_pool, retention_count, retention_days)
+            .await
+    }
+
+    /// Restricts the nodes pinning the team apps to some regions, or to the team own nodes.
+    async fn set_pinning_constraints(
+        &self,
+        ctx: &Context<'_>,
+        team_id: String,
+        regions: Vec<String>,
+        own_nodes_only: bool,
+    ) -> Result<Team, String> {
+        let (state, team) = managed_team(ctx, &team_id).await?;
+
+        for region in &regions {
+            validate_region(region)?;
+        }
+        team.set_pinning_constraints(&state.db_pool, &regions, own_nodes_only)
+            .await
+    }
+
+    /// Chooses who the gateway serves the app to, PASSWORD mode requiring a password.
+    ///
+    /// Sessions and signed links issued so far are revoked, setting the same mode again
+    /// revokes them without changing anything else.
+    async fn set_access_mode(
+        &self,
+        ctx: &Context<'_>,
+        app_id: String,
+        mode: String,
+        password: Option<String>,
+    ) -> Result<App, String> {
+        let (state, app) = managed_app(ctx, &app_id).await?;
+
+        let mode = AccessMode::from_name(&mode)?;
+        validate_access(mode, password.as_deref())?;
+        let password_hash = match password {
+            Some(password) => Some(hash_password(password).await.map_err(|e| e.to_string())?),
+            None => None,
+        };
+
+        let app = app.set_access(&state.db_pool, mode, password_hash).await?;
+        forget_app(&state.app_registry, &app.name);
+        println!(
+            "[Access] Access mode of app \"{}\" set to {}",
+            app.name,
+            mode.name()
+        );
+
+        Ok(app)
+    }
+
+    /// Signs a link token that opens an app served in TOKEN mode, passed as the `access_token`
+    /// query parameter.
+    async fn create_access_link(
+        &self,
+        ctx: &Context<'_>,
+        app_id: String,
+        expires_in_seconds: Option<i32>,
+    ) -> Result<String, String> {
+        let (state, app) = managed_app(ctx, &app_id).await?;
+        if app.access_mode != AccessMode::TOKEN {
+            return Err("Access links only open apps served in TOKEN mode".to_string());
+        }
+
+        let max_ttl = state.server_settings.access.max_link_ttl_seconds;
+        let ttl = match expires_in_seconds {
+            Some(seconds) if seconds <= 0 || seconds as u64 > max_ttl => {
+                return Err(format!(
+                    "Access links expire within 1 to {} seconds",
+                    max_ttl
+                ));
+            }
+            Some(seconds) => seconds as u64,
+            None => max_ttl,
+        };
+
+        let (token, _) = issue_access_token(
+            &state.server_settings.server.jwt_secret,
+            &AppAccess::from(&app),
+            AccessScope::Link,
+            ttl,
+        )?;
+        Ok(token)
+    }
+
+    /// Signs a short-lived session token for a team member to open an app served in TEAM mode
+    /// from a browser, passed as the `access_token` query parameter.
+    async fn create_team_session(
+        &self,
+        ctx: &Context<'_>,
+        app_id: String,
+    ) -> Result<String, String> {
+        let (state, app) = managed_app(ctx, &app_id).await?;
+        if app.access_mode != AccessMode::TEAM {
+            return Err("Team sessions only open apps served in TEAM mode".to_string());
+        }
+
+        let (token, _) = issue_access_token(
+            &state.server_settings.server.jwt_secret,
+            &AppAccess::from(&app),
+            AccessScope::Session,
+            TEAM_SIGN_IN_TTL_SECONDS,
+        )?;
+        Ok(token)
+    }
+}
+
+/// Server state and signed in user of a mutation.
+fn state_and_claims<'a>(ctx: &Context<'a>) -> Result<(&'a ServerState, &'a Claims), String> {
+    let state = ctx
+        .data::<ServerState>()
+        .map_err(|_| "Failed to get server state".to_string())?;
+    let claims = ctx
+        .data::<Claims>()
+        .map_err(|_| "User not connected".to_string())?;
+    Ok((state, claims))
+}
+
+/// Loads an app, if the signed in user manages it.
+async fn managed_app<'a>(
+    ctx: &Context<'a>,
+    app_id: &str,
+) -> Result<(&'a ServerState, App), String> {
+    let (state, claims) = state_and_claims(ctx)?;
+    let app = App::find_by_id(&state.db_pool, &app_id.to_string()).await?;
+    if !app.is_managed_by(&state.db_pool, claims).await? {
+        return Err("You do not have permission to perform this action.".to_string());
+    }
+    Ok((state, app))
+}
+
+/// Loads a team, if the signed in user is a member of it.
+async fn managed_team<'a>(
+    ctx: &Context<'a>,
+    team_id: &str,
+) -> Result<(&'a ServerState, Team), String> {
+    let (state, claims) = state_and_claims(ctx)?;
+    let team = Team::find_by_id(&state.db_pool, &team_id.to_string()).await?;
+    if !Team::is_managed_by(&state.db_pool, &team.id, claims).await? {
+        return Err("You do not have permission to perform this action.".to_string());
+    }
+    Ok((state, team))
+}
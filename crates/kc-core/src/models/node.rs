@@ -1,12 +1,17 @@
 use async_graphql::{Context, Object};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize, ser::SerializeStruct};
-use sqlx::{QueryBuilder, prelude::FromRow, types::Uuid};
+use sqlx::{
+    QueryBuilder,
+    prelude::{FromRow, Type},
+    types::Uuid,
+};
 use struct_iterable::Iterable;
 
 use crate::{
     database::DbPool,
-    models::{app::App, deployment::Deployment, team::Team},
+    models::{app::App, deployment::Deployment, node_status_change::NodeStatusChange, team::Team},
+    node::find_node_info,
     payloads::node::{CreateNodePayload, UpdateNodePayload},
     server::ServerState,
 };
@@ -14,6 +19,51 @@ use crate::{
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NodeInfo {
     pub last_seen: Option<i64>,
+    /// Reported with the last heartbeat, absent for nodes that do not send it
+    pub telemetry: Option<NodeTelemetry>,
+}
+
+/// State of a node and of its IPFS daemon, as reported in its heartbeats.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NodeTelemetry {
+    pub disk_free_bytes: u64,
+    pub disk_used_bytes: u64,
+    pub pinned_cids: u64,
+    pub repo_size_bytes: u64,
+    /// Version of the node software
+    pub version: String,
+    pub peer_id: String,
+    pub multiaddrs: Vec<String>,
+    /// Percentage of the CPU in use
+    pub cpu_load: f64,
+    /// Percentage of the memory in use
+    pub memory_load: f64,
+    /// Bytes received since the IPFS daemon started
+    pub bandwidth_in_bytes: u64,
+    /// Bytes sent since the IPFS daemon started
+    pub bandwidth_out_bytes: u64,
+}
+
+/// Liveness of a node, derived from its last heartbeat by the health checker.
+#[derive(Debug, Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "node_status")]
+pub enum NodeStatus {
+    #[sqlx(rename = "ONLINE")]
+    ONLINE,
+    #[sqlx(rename = "DEGRADED")]
+    DEGRADED,
+    #[sqlx(rename = "OFFLINE")]
+    OFFLINE,
+}
+
+impl NodeStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeStatus::ONLINE => "ONLINE",
+            NodeStatus::DEGRADED => "DEGRADED",
+            NodeStatus::OFFLINE => "OFFLINE",
+        }
+    }
 }
 
 #[derive(FromRow, Debug)]
@@ -23,7 +73,14 @@ pub struct Node {
     pub name: String,
     pub ip: String,
     pub port: i32,
+    /// Declared by the owner, replicas of an app are spread across regions
+    pub region: Option<String>,
     pub reputation_score: f64,
+    /// Key of the HMAC signing the node requests, never sent back after it is issued
+    pub secret: Option<String>,
+    pub secret_rotated_at: Option<DateTime<Utc>>,
+    pub status: NodeStatus,
+    pub status_changed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -34,6 +91,13 @@ pub struct NodeData {
     pub info: Option<NodeInfo>,
 }
 
+/// A node along with its secret, only returned when the secret is issued.
+#[derive(Serialize)]
+pub struct NodeCredentials {
+    pub node: Node,
+    pub secret: String,
+}
+
 impl Serialize for Node {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -45,7 +109,17 @@ impl Serialize for Node {
         state.serialize_field("name", &self.name)?;
         state.serialize_field("ip", &self.ip)?;
         state.serialize_field("port", &self.port)?;
+        state.serialize_field("region", &self.region)?;
         state.serialize_field("reputation_score", &self.reputation_score)?;
+        state.serialize_field("status", &self.status)?;
+        state.serialize_field(
+            "status_changed_at",
+            &self.status_changed_at.map(|date| date.to_string()),
+        )?;
+        state.serialize_field(
+            "secret_rotated_at",
+            &self.secret_rotated_at.map(|date| date.to_string()),
+        )?;
         state.serialize_field("created_at", &self.created_at.to_string())?;
         state.serialize_field("updated_at", &self.updated_at.to_string())?;
         state.end()
@@ -57,12 +131,14 @@ impl Node {
         match Uuid::parse_str(payload.owner_id.as_str()) {
             Ok(owner_id) => {
                 match sqlx::query_as::<_, Node>(
-                    "INSERT INTO nodes (owner_id, name, ip, port) VALUES ($1, $2, $3, $4) RETURNING *",
+                    "INSERT INTO nodes (owner_id, name, ip, port, region, secret, secret_rotated_at) VALUES ($1, $2, $3, $4, $5, $6, NOW()) RETURNING *",
                 )
                 .bind(owner_id)
                 .bind(payload.name.clone())
                 .bind(payload.ip.clone())
                 .bind(payload.port)
+                .bind(payload.region.clone())
+                .bind(generate_node_secret())
                 .fetch_one(db_pool)
                 .await
                 {
@@ -90,6 +166,16 @@ impl Node {
         }
     }
 
+    pub async fn find_all(db_pool: &DbPool) -> Result<Vec<Node>, String> {
+        match sqlx::query_as::<_, Node>("SELECT * FROM nodes")
+            .fetch_all(db_pool)
+            .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     pub async fn find_by_user_id(db_pool: &DbPool, id: &String) -> Result<Vec<Node>, String> {
         match Uuid::parse_str(id) {
             Ok(uuid) => {
@@ -145,6 +231,57 @@ impl Node {
         }
     }
 
+    /// Moves the node to a new status, recording the change in its status history.
+    pub async fn set_status(
+        &self,
+        db_pool: &DbPool,
+        status: NodeStatus,
+        last_seen: Option<DateTime<Utc>>,
+    ) -> Result<Node, String> {
+        match sqlx::query_as::<_, Node>(
+            "WITH change AS (INSERT INTO node_status_changes (node_id, status, previous_status, last_seen) VALUES ($1, $2, $3, $4)) UPDATE nodes SET status = $2, status_changed_at = NOW() WHERE id = $1 RETURNING *",
+        )
+        .bind(self.id)
+        .bind(status)
+        .bind(self.status)
+        .bind(last_seen)
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Replaces the secret of the node, requests signed with the previous one being rejected.
+    pub async fn rotate_secret(&self, db_pool: &DbPool) -> Result<Node, String> {
+        match sqlx::query_as::<_, Node>(
+            "UPDATE nodes SET secret = $1, secret_rotated_at = NOW() WHERE id = $2 RETURNING *",
+        )
+        .bind(generate_node_secret())
+        .bind(self.id)
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Drops the secret of the node, which cannot sign requests until a new one is issued.
+    pub async fn revoke_secret(&self, db_pool: &DbPool) -> Result<Node, String> {
+        match sqlx::query_as::<_, Node>(
+            "UPDATE nodes SET secret = NULL, secret_rotated_at = NOW() WHERE id = $1 RETURNING *",
+        )
+        .bind(self.id)
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     pub async fn delete_by_id(db_pool: &DbPool, id: &String) -> Result<Node, String> {
         match Uuid::parse_str(id) {
             Ok(uuid) => {
@@ -162,6 +299,10 @@ impl Node {
     }
 }
 
+fn generate_node_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
 #[Object]
 impl Node {
     async fn id(&self) -> Uuid {
@@ -176,9 +317,18 @@ impl Node {
     async fn port(&self) -> i32 {
         self.port
     }
+    async fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
     async fn reputation_score(&self) -> f64 {
         self.reputation_score
     }
+    async fn status(&self) -> &str {
+        self.status.name()
+    }
+    async fn status_changed_at(&self) -> Option<DateTime<Utc>> {
+        self.status_changed_at
+    }
     async fn created_at(&self) -> DateTime<Utc> {
         self.created_at
     }
@@ -245,4 +395,75 @@ impl Node {
             Err(e) => Err(e.to_string()),
         }
     }
+
+    async fn status_changes(&self, ctx: &Context<'_>) -> Result<Vec<NodeStatusChange>, String> {
+        let state = match ctx.data::<ServerState>() {
+            Ok(state) => state,
+            Err(_) => {
+                return Err("Failed to get server state".to_string());
+            }
+        };
+
+        NodeStatusChange::find_by_node_id(&state.db_pool, &self.id).await
+    }
+
+    /// Last heartbeat and telemetry, none once the heartbeat has expired.
+    async fn info(&self, ctx: &Context<'_>) -> Result<Option<NodeInfo>, String> {
+        let state = match ctx.data::<ServerState>() {
+            Ok(state) => state,
+            Err(_) => {
+                return Err("Failed to get server state".to_string());
+            }
+        };
+
+        find_node_info(&state.redis_client, &self.id).await
+    }
+}
+
+#[Object]
+impl NodeInfo {
+    async fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.last_seen
+            .and_then(|last_seen| DateTime::from_timestamp(last_seen, 0))
+    }
+    async fn telemetry(&self) -> Option<&NodeTelemetry> {
+        self.telemetry.as_ref()
+    }
+}
+
+#[Object]
+impl NodeTelemetry {
+    async fn disk_free_bytes(&self) -> u64 {
+        self.disk_free_bytes
+    }
+    async fn disk_used_bytes(&self) -> u64 {
+        self.disk_used_bytes
+    }
+    async fn pinned_cids(&self) -> u64 {
+        self.pinned_cids
+    }
+    async fn repo_size_bytes(&self) -> u64 {
+        self.repo_size_bytes
+    }
+    async fn version(&self) -> &str {
+        &self.version
+    }
+    async fn peer_id(&self) -> &str {
+        &self.peer_id
+    }
+    async fn multiaddrs(&self) -> &[String] {
+        &self.multiaddrs
+    }
+    async fn cpu_load(&self) -> f64 {
+        self.cpu_load
+    }
+    async fn memory_load(&self) -> f64 {
+        self.memory_load
+    }
+    async fn bandwidth_in_bytes(&self) -> u64 {
+        self.bandwidth_in_bytes
+    }
+    async fn bandwidth_out_bytes(&self) -> u64 {
+        self.bandwidth_out_bytes
+    }
 }
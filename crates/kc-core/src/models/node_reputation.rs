@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::ser::{Serialize, SerializeStruct};
+use sqlx::{
+    prelude::FromRow,
+    types::{Json, Uuid},
+};
+
+use crate::{database::DbPool, reputation::ReputationBreakdown};
+
+// Hourly scores over a few days, older ones are still kept in the table
+const REPUTATION_HISTORY_LIMIT: i64 = 100;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct NodeReputation {
+    pub id: Uuid,
+    pub node_id: Uuid,
+    pub score: f64,
+    pub breakdown: Json<ReputationBreakdown>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Serialize for NodeReputation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("NodeReputation", 5)?;
+        state.serialize_field("id", &self.id.to_string())?;
+        state.serialize_field("node_id", &self.node_id.to_string())?;
+        state.serialize_field("score", &self.score)?;
+        state.serialize_field("breakdown", &self.breakdown.0)?;
+        state.serialize_field("created_at", &self.created_at.to_string())?;
+        state.end()
+    }
+}
+
+impl NodeReputation {
+    /// Records a new score in the history and makes it the reputation of the node.
+    pub async fn record(
+        db_pool: &DbPool,
+        node_id: &Uuid,
+        score: f64,
+        breakdown: &ReputationBreakdown,
+    ) -> Result<NodeReputation, String> {
+        match sqlx::query_as::<_, NodeReputation>(
+            "WITH node AS (UPDATE nodes SET reputation_score = $2 WHERE id = $1) INSERT INTO node_reputation_scores (node_id, score, breakdown) VALUES ($1, $2, $3) RETURNING *",
+        )
+        .bind(node_id)
+        .bind(score)
+        .bind(Json(breakdown))
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub async fn find_latest_by_node_id(
+        db_pool: &DbPool,
+        node_id: &Uuid,
+    ) -> Result<Option<NodeReputation>, String> {
+        match sqlx::query_as::<_, NodeReputation>(
+            "SELECT * FROM node_reputation_scores WHERE node_id = $1 ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(node_id)
+        .fetch_optional(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Latest scores of a node, newest first.
+    pub async fn find_by_node_id(
+        db_pool: &DbPool,
+        node_id: &Uuid,
+    ) -> Result<Vec<NodeReputation>, String> {
+        match sqlx::query_as::<_, NodeReputation>(
+            "SELECT * FROM node_reputation_scores WHERE node_id = $1 ORDER BY created_at DESC LIMIT $2",
+        )
+        .bind(node_id)
+        .bind(REPUTATION_HISTORY_LIMIT)
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
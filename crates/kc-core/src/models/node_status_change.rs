@@ -0,0 +1,94 @@
+use async_graphql::Object;
+use chrono::{DateTime, Utc};
+use serde::ser::{Serialize, SerializeStruct};
+use sqlx::{prelude::FromRow, types::Uuid};
+
+use crate::{database::DbPool, models::node::NodeStatus};
+
+// Enough to see a flapping node, older changes are still kept in the table
+const STATUS_HISTORY_LIMIT: i64 = 100;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct NodeStatusChange {
+    pub id: Uuid,
+    pub node_id: Uuid,
+    pub status: NodeStatus,
+    pub previous_status: NodeStatus,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Serialize for NodeStatusChange {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("NodeStatusChange", 6)?;
+        state.serialize_field("id", &self.id.to_string())?;
+        state.serialize_field("node_id", &self.node_id.to_string())?;
+        state.serialize_field("status", &self.status)?;
+        state.serialize_field("previous_status", &self.previous_status)?;
+        state.serialize_field("last_seen", &self.last_seen.map(|date| date.to_string()))?;
+        state.serialize_field("created_at", &self.created_at.to_string())?;
+        state.end()
+    }
+}
+
+impl NodeStatusChange {
+    /// Latest status changes of a node, newest first.
+    pub async fn find_by_node_id(
+        db_pool: &DbPool,
+        node_id: &Uuid,
+    ) -> Result<Vec<NodeStatusChange>, String> {
+        match sqlx::query_as::<_, NodeStatusChange>(
+            "SELECT * FROM node_status_changes WHERE node_id = $1 ORDER BY created_at DESC LIMIT $2",
+        )
+        .bind(node_id)
+        .bind(STATUS_HISTORY_LIMIT)
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Status changes of a node since the given date, oldest first, along with the last change
+    /// before it, which tells the status at that date.
+    pub async fn find_since(
+        db_pool: &DbPool,
+        node_id: &Uuid,
+        since: &DateTime<Utc>,
+    ) -> Result<Vec<NodeStatusChange>, String> {
+        match sqlx::query_as::<_, NodeStatusChange>(
+            "SELECT * FROM node_status_changes WHERE node_id = $1 AND created_at >= COALESCE((SELECT MAX(created_at) FROM node_status_changes WHERE node_id = $1 AND created_at < $2), $2) ORDER BY created_at ASC",
+        )
+        .bind(node_id)
+        .bind(since)
+        .fetch_all(db_pool)
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[Object]
+impl NodeStatusChange {
+    async fn id(&self) -> Uuid {
+        self.id
+    }
+    async fn status(&self) -> &str {
+        self.status.name()
+    }
+    async fn previous_status(&self) -> &str {
+        self.previous_status.name()
+    }
+    async fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.last_seen
+    }
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
@@ -1,9 +1,13 @@
-use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+use async_graphql::{Context, EmptySubscription, Object, Schema};
 use sqlx::types::Uuid;
 
-use crate::{authentication::Claims, models::user::User, server::ServerState};
+use crate::{
+    authentication::Claims,
+    models::{mutation::Mutation, user::User},
+    server::ServerState,
+};
 
-pub type AppSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+pub type AppSchema = Schema<Query, Mutation, EmptySubscription>;
 pub struct Query;
 
 #[Object]
@@ -73,5 +77,5 @@ impl Query {
 }
 
 pub fn build_schema() -> AppSchema {
-    Schema::build(Query, EmptyMutation, EmptySubscription).finish()
+    Schema::build(Query, Mutation, EmptySubscription).finish()
 }
@@ -5,6 +5,7 @@ use sqlx::{QueryBuilder, prelude::FromRow, types::Uuid};
 use struct_iterable::Iterable;
 
 use crate::{
+    authentication::Claims,
     database::DbPool,
     models::{app::App, node::Node, user::User},
     payloads::team::{CreateTeamPayload, UpdateTeamPayload},
@@ -15,6 +16,10 @@ use crate::{
 pub struct Team {
     pub id: Uuid,
     pub name: String,
+    /// Regions the replicas of the team apps may be pinned in, anywhere when empty
+    pub pinning_regions: Vec<String>,
+    /// Only pins the team apps on nodes owned by the team
+    pub pin_on_own_nodes: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -27,6 +32,8 @@ impl Serialize for Team {
         let mut state = serializer.serialize_struct("Team", 5)?;
         state.serialize_field("id", &self.id.to_string())?;
         state.serialize_field("name", &self.name)?;
+        state.serialize_field("pinning_regions", &self.pinning_regions)?;
+        state.serialize_field("pin_on_own_nodes", &self.pin_on_own_nodes)?;
         state.serialize_field("created_at", &self.created_at.to_string())?;
         state.serialize_field("updated_at", &self.updated_at.to_string())?;
         state.end()
@@ -34,6 +41,32 @@ impl Serialize for Team {
 }
 
 impl Team {
+    /// Admins and members of a team can manage its apps and nodes.
+    pub async fn is_managed_by(
+        db_pool: &DbPool,
+        team_id: &Uuid,
+        claims: &Claims,
+    ) -> Result<bool, String> {
+        if claims.role == "admin" {
+            return Ok(true);
+        }
+
+        match Uuid::parse_str(&claims.user_id) {
+            Ok(user_id) => {
+                match sqlx::query("SELECT 1 FROM team_users WHERE team_id = $1 AND user_id = $2")
+                    .bind(team_id)
+                    .bind(user_id)
+                    .fetch_optional(db_pool)
+                    .await
+                {
+                    Ok(result) => Ok(result.is_some()),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Err(e) => Err(format!("Invalid UUID format: {}", e)),
+        }
+    }
+
     pub async fn create(db_pool: &DbPool, payload: &CreateTeamPayload) -> Result<Team, String> {
         match sqlx::query_as::<_, Team>("INSERT INTO teams (name) VALUES ($1) RETURNING *")
             .bind(payload.name.clone())
@@ -132,6 +165,27 @@ impl Team {
         }
     }
 
+    /// Restricts where the replicas of the team apps are pinned, from the next placement on.
+    pub async fn set_pinning_constraints(
+        &self,
+        db_pool: &DbPool,
+        regions: &[String],
+        own_nodes: bool,
+    ) -> Result<Team, String> {
+        match sqlx::query_as::<_, Team>(
+            "UPDATE teams SET pinning_regions = $1, pin_on_own_nodes = $2 WHERE id = $3 RETURNING *",
+        )
+        .bind(regions)
+        .bind(own_nodes)
+        .bind(self.id)
+        .fetch_one(db_pool)
+        .await
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     pub async fn associate_user(&self, db_pool: &DbPool, user: &User) -> Result<(), String> {
         match sqlx::query("INSERT INTO team_users (team_id, user_id) VALUES ($1, $2)")
             .bind(self.id)
@@ -169,6 +223,12 @@ impl Team {
     async fn name(&self) -> &str {
         &self.name
     }
+    async fn pinning_regions(&self) -> &[String] {
+        &self.pinning_regions
+    }
+    async fn pin_on_own_nodes(&self) -> bool {
+        self.pin_on_own_nodes
+    }
     async fn created_at(&self) -> DateTime<Utc> {
         self.created_at
     }
@@ -1,7 +1,332 @@
+use axum::{
+    Json,
+    body::{Bytes, to_bytes},
+    extract::{FromRequest, Request},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use redis::{AsyncTypedCommands, ExistenceCheck, SetExpiry, SetOptions};
 use serde::Deserialize;
+use sha2::Sha256;
+use sqlx::types::Uuid;
+
+use crate::{
+    events::NodeEvent,
+    json::ErrorJsonResponse,
+    models::node::{Node, NodeInfo, NodeStatus, NodeTelemetry},
+    redis::RedisClient,
+    server::ServerState,
+};
+
+pub const X_NODE_ID: &str = "x-node-id";
+pub const X_NODE_TIMESTAMP: &str = "x-node-timestamp";
+pub const X_NODE_SIGNATURE: &str = "x-node-signature";
+
+// Node requests are small JSON documents, anything bigger is not worth hashing
+const MAX_SIGNED_BODY_SIZE: usize = 64 * 1024;
+
+const MAX_VERSION_LENGTH: usize = 64;
+const MAX_PEER_ID_LENGTH: usize = 128;
+// A daemon listens on a handful of transports, each announced on a few interfaces
+const MAX_MULTIADDRS: usize = 32;
+const MAX_MULTIADDR_LENGTH: usize = 256;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct NodeHealthConfig {
+    /// Lifetime of the heartbeat key in Redis, past which a node is offline
     pub staleness_seconds: u64,
+    /// Age of the last heartbeat past which a node is degraded
+    pub degraded_after_seconds: u64,
     pub check_interval_seconds: u64,
 }
+
+/// Status of a node from the Unix timestamp of its last heartbeat.
+pub fn node_status(config: &NodeHealthConfig, last_seen: Option<i64>, now: i64) -> NodeStatus {
+    match last_seen.map(|last_seen| (now - last_seen).max(0) as u64) {
+        Some(age) if age <= config.degraded_after_seconds => NodeStatus::ONLINE,
+        Some(age) if age <= config.staleness_seconds => NodeStatus::DEGRADED,
+        _ => NodeStatus::OFFLINE,
+    }
+}
+
+/// Rejects telemetry that is malformed or out of range before it is stored.
+pub fn validate_telemetry(telemetry: &NodeTelemetry) -> Result<(), String> {
+    if telemetry.version.is_empty()
+        || telemetry.version.len() > MAX_VERSION_LENGTH
+        || telemetry.version.chars().any(char::is_control)
+    {
+        return Err(format!(
+            "Version must be between 1 and {} printable characters",
+            MAX_VERSION_LENGTH
+        ));
+    }
+
+    // Peer ids are base58btc multihashes or base32 CIDs
+    if telemetry.peer_id.is_empty()
+        || telemetry.peer_id.len() > MAX_PEER_ID_LENGTH
+        || !telemetry.peer_id.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err("Invalid peer id".to_string());
+    }
+
+    if telemetry.multiaddrs.len() > MAX_MULTIADDRS {
+        return Err(format!(
+            "At most {} multiaddrs can be reported",
+            MAX_MULTIADDRS
+        ));
+    }
+    if let Some(multiaddr) = telemetry.multiaddrs.iter().find(|multiaddr| {
+        !multiaddr.starts_with('/')
+            || multiaddr.len() > MAX_MULTIADDR_LENGTH
+            || multiaddr
+                .chars()
+                .any(|c| c.is_whitespace() || c.is_control())
+    }) {
+        return Err(format!("Invalid multiaddr: {}", multiaddr));
+    }
+
+    for (name, load) in [
+        ("CPU", telemetry.cpu_load),
+        ("Memory", telemetry.memory_load),
+    ] {
+        if !(0.0..=100.0).contains(&load) {
+            return Err(format!("{} load must be a percentage", name));
+        }
+    }
+
+    Ok(())
+}
+
+/// Last heartbeat of a node, none once its key has expired.
+pub async fn find_node_info(
+    redis_client: &RedisClient,
+    node_id: &Uuid,
+) -> Result<Option<NodeInfo>, String> {
+    let mut conn = redis_client
+        .get_multiplexed_tokio_connection()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn
+        .get(format!("nodes:{}", node_id))
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|info_json| serde_json::from_str::<NodeInfo>(&info_json).ok()))
+}
+
+pub fn start_health_checker(state: ServerState) {
+    tokio::spawn(async move {
+        let interval = state.server_settings.node_health.check_interval_seconds;
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if let Err(e) = check_nodes(&state).await {
+                eprintln!("[Health] Node health check error: {}", e);
+            }
+        }
+    });
+}
+
+/// Updates the status of every node from its heartbeat, recording and announcing changes.
+async fn check_nodes(state: &ServerState) -> Result<(), String> {
+    let nodes = Node::find_all(&state.db_pool).await?;
+    let mut conn = state
+        .redis_client
+        .get_multiplexed_tokio_connection()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let now = Utc::now().timestamp();
+    for node in nodes {
+        // An expired key means no heartbeat within the staleness window
+        let last_seen = conn
+            .get(format!("nodes:{}", node.id))
+            .await
+            .map_err(|e| e.to_string())?
+            .and_then(|info_json| serde_json::from_str::<NodeInfo>(&info_json).ok())
+            .and_then(|info| info.last_seen);
+
+        let status = node_status(&state.server_settings.node_health, last_seen, now);
+        if status == node.status {
+            continue;
+        }
+
+        let last_seen_at = last_seen.and_then(|last_seen| DateTime::from_timestamp(last_seen, 0));
+        let updated = match node.set_status(&state.db_pool, status, last_seen_at).await {
+            Ok(updated) => updated,
+            Err(e) => {
+                eprintln!("[Health] Error in status update of node {}: {}", node.id, e);
+                continue;
+            }
+        };
+        println!(
+            "[Health] Node {} is now {} (was {})",
+            updated.id,
+            status.name(),
+            node.status.name()
+        );
+
+        // Sending only fails when nobody is subscribed
+        let _ = state.node_event_bus.send(NodeEvent::Status {
+            node_id: updated.id.to_string(),
+            status,
+            previous_status: node.status,
+            last_seen,
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct NodeAuthConfig {
+    /// How far the timestamp of a signed request may be from the satellite clock
+    pub max_clock_skew_seconds: u64,
+}
+
+#[derive(Debug)]
+pub enum NodeAuthError {
+    MissingSignature,
+    InvalidSignature,
+    ExpiredSignature,
+    ReplayedSignature,
+    RevokedCredentials,
+    Unavailable,
+}
+
+impl IntoResponse for NodeAuthError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            NodeAuthError::MissingSignature => (
+                StatusCode::UNAUTHORIZED,
+                "Signed node request required".to_string(),
+            ),
+            NodeAuthError::InvalidSignature => (
+                StatusCode::UNAUTHORIZED,
+                "Invalid node signature".to_string(),
+            ),
+            NodeAuthError::ExpiredSignature => (
+                StatusCode::UNAUTHORIZED,
+                "Node request timestamp out of range".to_string(),
+            ),
+            NodeAuthError::ReplayedSignature => (
+                StatusCode::UNAUTHORIZED,
+                "Node request already received".to_string(),
+            ),
+            NodeAuthError::RevokedCredentials => (
+                StatusCode::FORBIDDEN,
+                "Node credentials revoked".to_string(),
+            ),
+            NodeAuthError::Unavailable => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Node authentication unavailable".to_string(),
+            ),
+        };
+        (status, Json(ErrorJsonResponse { error })).into_response()
+    }
+}
+
+/// A request signed by a registered node, with its raw body for the handler to parse.
+///
+/// Nodes send their id, a Unix timestamp and the hex HMAC-SHA256 of `{timestamp}.{body}`
+/// keyed with their secret. Each signature is only accepted once within the clock skew window.
+pub struct SignedNode {
+    pub node: Node,
+    pub body: Bytes,
+}
+
+impl SignedNode {
+    pub fn json<T: for<'de> Deserialize<'de>>(&self) -> Result<T, String> {
+        serde_json::from_slice(&self.body).map_err(|e| e.to_string())
+    }
+}
+
+impl FromRequest<ServerState> for SignedNode {
+    type Rejection = NodeAuthError;
+
+    async fn from_request(request: Request, state: &ServerState) -> Result<Self, Self::Rejection> {
+        let (parts, body) = request.into_parts();
+        let node_id = header_value(&parts.headers, X_NODE_ID)?;
+        let timestamp = header_value(&parts.headers, X_NODE_TIMESTAMP)?;
+        let signature = header_value(&parts.headers, X_NODE_SIGNATURE)?;
+
+        let max_skew = state.server_settings.node_auth.max_clock_skew_seconds;
+        let signed_at = timestamp
+            .parse::<i64>()
+            .map_err(|_| NodeAuthError::InvalidSignature)?;
+        if (Utc::now().timestamp() - signed_at).unsigned_abs() > max_skew {
+            return Err(NodeAuthError::ExpiredSignature);
+        }
+
+        let body = to_bytes(body, MAX_SIGNED_BODY_SIZE)
+            .await
+            .map_err(|_| NodeAuthError::InvalidSignature)?;
+        let node = Node::find_by_id(&state.db_pool, &node_id)
+            .await
+            .map_err(|_| NodeAuthError::InvalidSignature)?;
+        let secret = node
+            .secret
+            .as_deref()
+            .ok_or(NodeAuthError::RevokedCredentials)?;
+        if !verify_node_signature(secret, &timestamp, &body, &signature) {
+            println!(
+                "[API-Nodes] Rejected request with invalid signature: node={}",
+                node.id
+            );
+            return Err(NodeAuthError::InvalidSignature);
+        }
+
+        // Signatures outlive the skew window on both sides of the satellite clock
+        let mut conn = state
+            .redis_client
+            .get_multiplexed_tokio_connection()
+            .await
+            .map_err(|_| NodeAuthError::Unavailable)?;
+        let first_use = conn
+            .set_options(
+                format!("nodes:signatures:{}", signature.to_ascii_lowercase()),
+                1,
+                SetOptions::default()
+                    .conditional_set(ExistenceCheck::NX)
+                    .with_expiration(SetExpiry::EX(max_skew * 2)),
+            )
+            .await
+            .map_err(|_| NodeAuthError::Unavailable)?;
+        if first_use.is_none() {
+            println!("[API-Nodes] Rejected replayed request: node={}", node.id);
+            return Err(NodeAuthError::ReplayedSignature);
+        }
+
+        Ok(SignedNode { node, body })
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Result<String, NodeAuthError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .ok_or(NodeAuthError::MissingSignature)
+}
+
+fn verify_node_signature(secret: &str, timestamp: &str, body: &[u8], signature: &str) -> bool {
+    match hex::decode(signature) {
+        // Constant time comparison
+        Ok(signature) => node_mac(secret, timestamp, body)
+            .verify_slice(&signature)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn node_mac(secret: &str, timestamp: &str, body: &[u8]) -> Hmac<Sha256> {
+    // HMAC accepts keys of any size
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
@@ -13,4 +13,5 @@ pub struct UpdateAppPayload {
     pub name: Option<String>,
     pub key_name: Option<String>,
     pub ipns_name: Option<String>,
+    pub replication_factor: Option<i32>,
 }
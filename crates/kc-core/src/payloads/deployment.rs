@@ -1,12 +1,16 @@
 use serde::Deserialize;
 use struct_iterable::Iterable;
 
-use crate::models::deployment::DeploymentStatus;
+use crate::{models::deployment::DeploymentStatus, site_rules::SiteRules};
 
 #[derive(Deserialize, Debug)]
 pub struct CreateDeploymentPayload {
     pub app_id: String,
     pub cid: String,
+    pub file_count: i64,
+    pub total_size: i64,
+    pub source_deployment_id: Option<String>,
+    pub site_rules: Option<SiteRules>,
 }
 
 #[derive(Deserialize, Debug, Iterable)]
@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct CreateDeploymentAliasPayload {
+    pub app_id: String,
+    pub deployment_id: String,
+    pub name: String,
+}
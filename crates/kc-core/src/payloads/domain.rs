@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct CreateDomainPayload {
+    pub app_id: String,
+    pub hostname: String,
+}
@@ -1,6 +1,8 @@
 pub mod app;
 pub mod deployment;
+pub mod deployment_alias;
 pub mod deployment_node;
+pub mod domain;
 pub mod node;
 pub mod team;
 pub mod user;
@@ -1,12 +1,15 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use struct_iterable::Iterable;
 
+use crate::models::deployment_node::PinStatus;
+
 #[derive(Deserialize, Debug)]
 pub struct CreateNodePayload {
     pub owner_id: String,
     pub name: String,
     pub ip: Option<String>,
     pub port: i32,
+    pub region: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Iterable)]
@@ -15,4 +18,25 @@ pub struct UpdateNodePayload {
     pub name: Option<String>,
     pub ip: Option<String>,
     pub port: Option<i32>,
+    pub region: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct NodeDeployPayload {
+    pub name: String,
+    pub cid: String,
+    pub deployment_node_id: String,
+    pub callback_token: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct NodeUnpinPayload {
+    pub cid: String,
+    pub deployment_node_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PinConfirmationPayload {
+    pub token: String,
+    pub status: PinStatus,
 }
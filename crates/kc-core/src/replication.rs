@@ -0,0 +1,272 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use redis::AsyncTypedCommands;
+use serde::Deserialize;
+use sqlx::types::Uuid;
+
+use crate::{
+    jobs::{JobKind, enqueue},
+    models::{
+        app::App,
+        deployment::{Deployment, DeploymentStatus},
+        deployment_node::{DeploymentNode, PinStatus},
+        node::Node,
+        team::Team,
+    },
+    node::find_node_info,
+    payloads::deployment_node::{CreateDeploymentNodePayload, UpdateDeploymentNodePayload},
+    redis::RedisClient,
+    selection::{NodeCandidate, Placement, SelectionStrategy, node_selector},
+    server::ServerState,
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReplicationConfig {
+    pub min_reputation: f64,
+    pub selection_strategy: SelectionStrategy,
+    pub max_factor: i32,
+    pub reconcile_interval_seconds: u64,
+}
+
+pub fn validate_replication_factor(config: &ReplicationConfig, factor: i32) -> Result<(), String> {
+    if factor < 1 || factor > config.max_factor {
+        return Err(format!(
+            "Replication factor must be between 1 and {}",
+            config.max_factor
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the nodes among `node_ids` whose heartbeat has not expired.
+pub async fn live_node_ids(
+    redis_client: &RedisClient,
+    node_ids: &[Uuid],
+) -> Result<HashSet<Uuid>, String> {
+    let mut conn = redis_client
+        .get_multiplexed_tokio_connection()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut live = HashSet::new();
+    for node_id in node_ids {
+        if conn
+            .exists(format!("nodes:{}", node_id))
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            live.insert(*node_id);
+        }
+    }
+
+    Ok(live)
+}
+
+/// Picks up to `count` nodes for new replicas of a deployment, with the selection strategy
+/// of the config, `placed` being the nodes it was already sent to.
+pub async fn select_nodes(
+    state: &ServerState,
+    app: &App,
+    deployment: &Deployment,
+    count: usize,
+    placed: &[Uuid],
+) -> Result<Vec<Node>, String> {
+    let config = &state.server_settings.replication;
+    let team = Team::find_by_id(&state.db_pool, &app.team_id.to_string()).await?;
+    let placement = Placement {
+        size: deployment.total_size.max(0) as u64,
+        regions: team.pinning_regions.clone(),
+        owner_id: team.pin_on_own_nodes.then_some(team.id),
+    };
+
+    let nodes = Node::find_all(&state.db_pool).await?;
+    let mut candidates = Vec::new();
+    let mut placed_candidates = Vec::new();
+    for node in &nodes {
+        let is_placed = placed.contains(&node.id);
+        if !is_placed && node.reputation_score <= config.min_reputation {
+            continue;
+        }
+
+        let info = find_node_info(&state.redis_client, &node.id).await?;
+        let candidate = NodeCandidate {
+            id: node.id,
+            owner_id: node.owner_id,
+            region: node.region.clone(),
+            reputation: node.reputation_score,
+            online: info.is_some(),
+            disk_free_bytes: info
+                .and_then(|info| info.telemetry)
+                .map(|telemetry| telemetry.disk_free_bytes),
+        };
+        if is_placed {
+            placed_candidates.push(candidate);
+        } else {
+            candidates.push(candidate);
+        }
+    }
+
+    let selected = node_selector(config.selection_strategy).select(
+        &candidates,
+        &placed_candidates,
+        count,
+        &placement,
+        &mut rand::rng(),
+    );
+
+    let mut nodes = nodes;
+    Ok(selected
+        .iter()
+        .filter_map(|id| {
+            let index = nodes.iter().position(|node| node.id == *id)?;
+            Some(nodes.swap_remove(index))
+        })
+        .collect())
+}
+
+/// Records a pin of the deployment on each node and queues the pin requests.
+pub async fn schedule_pins(state: &ServerState, deployment: &Deployment, nodes: &[Node]) -> usize {
+    let mut scheduled = 0;
+
+    for node in nodes {
+        let deployment_node = match DeploymentNode::create(
+            &state.db_pool,
+            &CreateDeploymentNodePayload {
+                deployment_id: deployment.id.to_string(),
+                node_id: node.id.to_string(),
+            },
+        )
+        .await
+        {
+            Ok(deployment_node) => deployment_node,
+            Err(e) => {
+                eprintln!("[Replication] Error creating deployment_node record: {}", e);
+                continue;
+            }
+        };
+
+        match enqueue(
+            &state.redis_client,
+            JobKind::PinNode {
+                deployment_node_id: deployment_node.id.to_string(),
+            },
+        )
+        .await
+        {
+            Ok(_) => scheduled += 1,
+            Err(e) => eprintln!(
+                "[Replication] Error in pin scheduling for node {}: {}",
+                node.id, e
+            ),
+        }
+    }
+
+    scheduled
+}
+
+pub fn start_reconciler(state: ServerState) {
+    tokio::spawn(async move {
+        let interval = state.server_settings.replication.reconcile_interval_seconds;
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if let Err(e) = reconcile(&state).await {
+                eprintln!("[Replication] Reconciliation error: {}", e);
+            }
+        }
+    });
+}
+
+async fn reconcile(state: &ServerState) -> Result<(), String> {
+    let deployments =
+        Deployment::find_by_status(&state.db_pool, DeploymentStatus::DEPLOYED).await?;
+
+    for deployment in deployments {
+        if let Err(e) = reconcile_deployment(state, &deployment).await {
+            eprintln!(
+                "[Replication] Deployment {} reconciliation error: {}",
+                deployment.id, e
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Brings the live replicas of a deployment back to the replication factor of its app.
+async fn reconcile_deployment(state: &ServerState, deployment: &Deployment) -> Result<(), String> {
+    let app = App::find_by_id(&state.db_pool, &deployment.app_id.to_string()).await?;
+    let factor = app.replication_factor.max(0) as usize;
+
+    let replicas = DeploymentNode::find_by_deployment_id(&state.db_pool, &deployment.id).await?;
+    let node_ids: Vec<Uuid> = replicas.iter().map(|replica| replica.node_id).collect();
+    let live = live_node_ids(&state.redis_client, &node_ids).await?;
+
+    let mut active = 0;
+    let mut recoverable = Vec::new();
+    for replica in &replicas {
+        let node_alive = live.contains(&replica.node_id);
+        match replica.status {
+            PinStatus::PINNING | PinStatus::PINNED if node_alive => active += 1,
+            PinStatus::PINNING | PinStatus::PINNED => {
+                replica
+                    .update(
+                        &state.db_pool,
+                        &UpdateDeploymentNodePayload {
+                            deployment_id: None,
+                            node_id: None,
+                            status: Some(PinStatus::LOST),
+                        },
+                    )
+                    .await?;
+                println!(
+                    "[Replication] Replica of app \"{}\" lost on node {}",
+                    app.name, replica.node_id
+                );
+            }
+            PinStatus::LOST if node_alive => recoverable.push(replica),
+            _ => {}
+        }
+    }
+
+    // Nodes coming back are asked to pin again before looking for new ones
+    for replica in recoverable {
+        if active >= factor {
+            break;
+        }
+        let replica = replica.restart(&state.db_pool).await?;
+        enqueue(
+            &state.redis_client,
+            JobKind::PinNode {
+                deployment_node_id: replica.id.to_string(),
+            },
+        )
+        .await?;
+        active += 1;
+    }
+
+    if active >= factor {
+        return Ok(());
+    }
+
+    let missing = factor - active;
+    let nodes = select_nodes(state, &app, deployment, missing, &node_ids).await?;
+    let scheduled = schedule_pins(state, deployment, &nodes).await;
+    if scheduled > 0 {
+        println!(
+            "[Replication] App \"{}\": {} new replica(s) scheduled",
+            app.name, scheduled
+        );
+    }
+    if scheduled < missing {
+        eprintln!(
+            "[Replication] App \"{}\" is under-replicated: {}/{} replicas",
+            app.name,
+            active + scheduled,
+            factor
+        );
+    }
+
+    Ok(())
+}
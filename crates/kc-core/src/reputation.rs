@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{
+        deployment_node::{DeploymentNode, PinStatus},
+        node::{Node, NodeStatus},
+        node_reputation::NodeReputation,
+        node_status_change::NodeStatusChange,
+    },
+    server::ServerState,
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReputationConfig {
+    pub update_interval_seconds: u64,
+    /// Only the history of the last days counts in the score
+    pub window_days: u64,
+    /// Age at which an event counts half as much as a current one
+    pub half_life_days: f64,
+    pub uptime_weight: f64,
+    pub pin_weight: f64,
+    pub latency_weight: f64,
+    pub content_weight: f64,
+}
+
+/// One criterion of the score, between 0 and 1, without value when there is nothing to judge.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReputationComponent {
+    pub value: Option<f64>,
+    pub weight: f64,
+    pub samples: u64,
+}
+
+/// How a score was computed, stored with it to explain it afterwards.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReputationBreakdown {
+    /// Time spent online, degraded counting half
+    pub uptime: ReputationComponent,
+    /// Share of pins confirmed rather than failed
+    pub pins: ReputationComponent,
+    /// Confirmation delay of pins, relative to the pin timeout
+    pub latency: ReputationComponent,
+    /// Share of confirmed replicas still held when checked
+    pub content: ReputationComponent,
+    pub window_days: u64,
+    pub half_life_days: f64,
+}
+
+impl ReputationBreakdown {
+    /// Weighted mean of the criteria having a value, a node without history keeps full marks.
+    pub fn score(&self) -> f64 {
+        let (total, weights) = [&self.uptime, &self.pins, &self.latency, &self.content]
+            .iter()
+            .filter_map(|component| component.value.map(|value| (value, component.weight)))
+            .fold((0.0, 0.0), |(total, weights), (value, weight)| {
+                (total + value * weight, weights + weight)
+            });
+
+        if weights > 0.0 {
+            (total / weights).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+pub fn start_reputation_updater(state: ServerState) {
+    tokio::spawn(async move {
+        let interval = state.server_settings.reputation.update_interval_seconds;
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if let Err(e) = update_reputations(&state).await {
+                eprintln!("[Reputation] Reputation update error: {}", e);
+            }
+        }
+    });
+}
+
+async fn update_reputations(state: &ServerState) -> Result<(), String> {
+    let nodes = Node::find_all(&state.db_pool).await?;
+    for node in nodes {
+        match update_reputation(state, &node).await {
+            Ok(reputation) if reputation.score != node.reputation_score => println!(
+                "[Reputation] Node {} scored {:.3} (was {:.3})",
+                node.id, reputation.score, node.reputation_score
+            ),
+            Ok(_) => {}
+            Err(e) => eprintln!(
+                "[Reputation] Error in reputation update of node {}: {}",
+                node.id, e
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// Scores a node from its recent history and records the result.
+pub async fn update_reputation(state: &ServerState, node: &Node) -> Result<NodeReputation, String> {
+    let config = &state.server_settings.reputation;
+    let now = Utc::now();
+    let since = (now - chrono::Duration::days(config.window_days as i64)).max(node.created_at);
+
+    let status_changes = NodeStatusChange::find_since(&state.db_pool, &node.id, &since).await?;
+    let pins = DeploymentNode::find_by_node_id_since(&state.db_pool, &node.id, &since).await?;
+
+    let breakdown = ReputationBreakdown {
+        uptime: uptime_component(config, node, &status_changes, since, now),
+        pins: pin_component(config, &pins, now),
+        latency: latency_component(
+            config,
+            &pins,
+            state.server_settings.deploy.pin_timeout_seconds,
+            now,
+        ),
+        content: content_component(config, &pins, now),
+        window_days: config.window_days,
+        half_life_days: config.half_life_days,
+    };
+
+    NodeReputation::record(&state.db_pool, &node.id, breakdown.score(), &breakdown).await
+}
+
+/// Weight of an event that happened at `date`, halving every half-life.
+fn decay(config: &ReputationConfig, date: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let age_days = (now - date).num_seconds().max(0) as f64 / 86400.0;
+    0.5_f64.powf(age_days / config.half_life_days)
+}
+
+/// Decayed weight of the time between `from` and `to`, recent time counting more.
+fn decayed_duration(
+    config: &ReputationConfig,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> f64 {
+    let half_life_seconds = config.half_life_days * 86400.0;
+    let rate = std::f64::consts::LN_2 / half_life_seconds;
+    let age_start = (now - from).num_seconds().max(0) as f64;
+    let age_end = (now - to).num_seconds().max(0) as f64;
+    ((-rate * age_end).exp() - (-rate * age_start).exp()) / rate
+}
+
+fn status_value(status: NodeStatus) -> f64 {
+    match status {
+        NodeStatus::ONLINE => 1.0,
+        NodeStatus::DEGRADED => 0.5,
+        NodeStatus::OFFLINE => 0.0,
+    }
+}
+
+/// `changes` holds the changes since `since`, oldest first, preceded by the one in effect then.
+fn uptime_component(
+    config: &ReputationConfig,
+    node: &Node,
+    changes: &[NodeStatusChange],
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> ReputationComponent {
+    let mut status = match changes.first() {
+        Some(change) if change.created_at <= since => change.status,
+        Some(change) => change.previous_status,
+        None => node.status,
+    };
+    let mut periods = Vec::new();
+    let mut start = since;
+    for change in changes.iter().filter(|change| change.created_at > since) {
+        periods.push((status, start, change.created_at));
+        status = change.status;
+        start = change.created_at;
+    }
+    periods.push((status, start, now));
+
+    let (total, weights) = periods
+        .iter()
+        .map(|(status, from, to)| {
+            (
+                status_value(*status),
+                decayed_duration(config, *from, *to, now),
+            )
+        })
+        .fold((0.0, 0.0), |(total, weights), (value, weight)| {
+            (total + value * weight, weights + weight)
+        });
+
+    ReputationComponent {
+        value: (weights > 0.0).then(|| total / weights),
+        weight: config.uptime_weight,
+        samples: periods.len() as u64,
+    }
+}
+
+/// Whether a pin succeeded, unknown while it is still in progress.
+fn pin_succeeded(pin: &DeploymentNode) -> Option<bool> {
+    match pin.status {
+        PinStatus::PINNED => Some(true),
+        PinStatus::FAILED => Some(false),
+        // Replicas lost or released after a confirmation were pinned all the same
+        PinStatus::LOST => Some(pin.confirmed_at.is_some()),
+        PinStatus::UNPINNED => pin.confirmed_at.map(|_| true),
+        PinStatus::PINNING => None,
+    }
+}
+
+fn pin_component(
+    config: &ReputationConfig,
+    pins: &[DeploymentNode],
+    now: DateTime<Utc>,
+) -> ReputationComponent {
+    let outcomes: Vec<(f64, f64)> = pins
+        .iter()
+        .filter_map(|pin| {
+            pin_succeeded(pin).map(|succeeded| {
+                (
+                    f64::from(u8::from(succeeded)),
+                    decay(config, pin.updated_at, now),
+                )
+            })
+        })
+        .collect();
+
+    weighted_component(&outcomes, config.pin_weight)
+}
+
+fn latency_component(
+    config: &ReputationConfig,
+    pins: &[DeploymentNode],
+    pin_timeout_seconds: u64,
+    now: DateTime<Utc>,
+) -> ReputationComponent {
+    let timeout = pin_timeout_seconds.max(1) as f64;
+    let latencies: Vec<(f64, f64)> = pins
+        .iter()
+        .filter_map(|pin| match (pin.requested_at, pin.confirmed_at) {
+            (Some(requested_at), Some(confirmed_at)) => {
+                let elapsed = (confirmed_at - requested_at).num_seconds().max(0) as f64;
+                Some((
+                    (1.0 - elapsed / timeout).clamp(0.0, 1.0),
+                    decay(config, confirmed_at, now),
+                ))
+            }
+            _ => None,
+        })
+        .collect();
+
+    weighted_component(&latencies, config.latency_weight)
+}
+
+/// Confirmed replicas found missing by the reconciler failed their content check.
+fn content_component(
+    config: &ReputationConfig,
+    pins: &[DeploymentNode],
+    now: DateTime<Utc>,
+) -> ReputationComponent {
+    let checks: Vec<(f64, f64)> = pins
+        .iter()
+        .filter(|pin| pin.confirmed_at.is_some())
+        .filter_map(|pin| {
+            let value = match pin.status {
+                PinStatus::PINNED | PinStatus::UNPINNED => 1.0,
+                PinStatus::LOST => 0.0,
+                PinStatus::PINNING | PinStatus::FAILED => return None,
+            };
+            Some((value, decay(config, pin.updated_at, now)))
+        })
+        .collect();
+
+    weighted_component(&checks, config.content_weight)
+}
+
+/// Weighted mean of `(value, decay)` samples.
+fn weighted_component(samples: &[(f64, f64)], weight: f64) -> ReputationComponent {
+    let (total, weights) = samples
+        .iter()
+        .fold((0.0, 0.0), |(total, weights), (value, decay)| {
+            (total + value * decay, weights + decay)
+        });
+
+    ReputationComponent {
+        value: (weights > 0.0).then(|| total / weights),
+        weight,
+        samples: samples.len() as u64,
+    }
+}
@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::{
+    jobs::{JobKind, enqueue},
+    models::{
+        app::App,
+        deployment::{Deployment, DeploymentStatus},
+        deployment_alias::DeploymentAlias,
+        deployment_node::DeploymentNode,
+    },
+    payloads::deployment::UpdateDeploymentPayload,
+    server::ServerState,
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RetentionConfig {
+    pub gc_interval_seconds: u64,
+}
+
+pub fn validate_retention(
+    retention_count: Option<i32>,
+    retention_days: Option<i32>,
+) -> Result<(), String> {
+    if retention_count.is_some_and(|count| count < 1) {
+        return Err("Retention count must be at least 1".to_string());
+    }
+    if retention_days.is_some_and(|days| days < 1) {
+        return Err("Retention days must be at least 1".to_string());
+    }
+
+    Ok(())
+}
+
+pub fn start_garbage_collector(state: ServerState) {
+    tokio::spawn(async move {
+        let interval = state.server_settings.retention.gc_interval_seconds;
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if let Err(e) = collect_garbage(&state).await {
+                eprintln!("[GC] Garbage collection error: {}", e);
+            }
+        }
+    });
+}
+
+async fn collect_garbage(state: &ServerState) -> Result<(), String> {
+    let apps = App::find_with_retention(&state.db_pool).await?;
+
+    for app in apps {
+        let deployments = Deployment::find_by_app_id(&state.db_pool, &app.id).await?;
+        // Aliased releases stay reachable through their permalinks
+        let aliased = DeploymentAlias::find_by_app_id(&state.db_pool, &app.id)
+            .await?
+            .into_iter()
+            .map(|alias| alias.deployment_id)
+            .collect::<Vec<_>>();
+        for deployment in expired_deployments(&app, &deployments)
+            .into_iter()
+            .filter(|deployment| !aliased.contains(&deployment.id))
+        {
+            if let Err(e) = archive_deployment(state, deployment).await {
+                eprintln!(
+                    "[GC] Error archiving deployment {} of app \"{}\": {}",
+                    deployment.id, app.name, e
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Deployments falling outside the app retention policy, newest first.
+///
+/// A deployment is kept while it is among the last `retention_count` ones or younger than
+/// `retention_days`. The live deployment and those still in progress are never returned.
+fn expired_deployments<'a>(app: &App, deployments: &'a [Deployment]) -> Vec<&'a Deployment> {
+    if app.retention_count.is_none() && app.retention_days.is_none() {
+        return Vec::new();
+    }
+
+    let now = Utc::now();
+    deployments
+        .iter()
+        .filter(|deployment| !matches!(deployment.status, DeploymentStatus::ARCHIVED))
+        .enumerate()
+        .filter(|(rank, deployment)| {
+            let kept_by_count = app
+                .retention_count
+                .is_some_and(|count| (*rank as i64) < count as i64);
+            let kept_by_age = app
+                .retention_days
+                .is_some_and(|days| (now - deployment.created_at).num_days() < days as i64);
+            !kept_by_count && !kept_by_age
+        })
+        .map(|(_, deployment)| deployment)
+        .filter(|deployment| {
+            matches!(
+                deployment.status,
+                DeploymentStatus::SUPERSEDED | DeploymentStatus::FAILED | DeploymentStatus::PREVIEW
+            )
+        })
+        .collect()
+}
+
+async fn archive_deployment(state: &ServerState, deployment: &Deployment) -> Result<(), String> {
+    // Rollbacks reuse CIDs, so the content may still back another deployment, in which case
+    // the pins are released with the last deployment using it
+    if !deployment.is_cid_shared(&state.db_pool).await? {
+        let pins =
+            DeploymentNode::find_unreleased_by_cid(&state.db_pool, &deployment.id, &deployment.cid)
+                .await?;
+        for pin in pins {
+            enqueue(
+                &state.redis_client,
+                JobKind::UnpinNode {
+                    deployment_node_id: pin.id.to_string(),
+                },
+            )
+            .await?;
+        }
+
+        state.ipfs.unpin(&deployment.cid).await?;
+    }
+
+    deployment
+        .update(
+            &state.db_pool,
+            &UpdateDeploymentPayload {
+                app_id: None,
+                cid: None,
+                status: Some(DeploymentStatus::ARCHIVED),
+            },
+        )
+        .await?;
+    println!(
+        "[GC] Deployment {} archived (CID: {})",
+        deployment.id, deployment.cid
+    );
+
+    Ok(())
+}
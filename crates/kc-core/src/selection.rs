@@ -0,0 +1,464 @@
+use std::collections::HashSet;
+
+use rand::{Rng, RngCore};
+use serde::Deserialize;
+use sqlx::types::Uuid;
+
+const MAX_REGION_LENGTH: usize = 64;
+
+// Nodes with a nearly full disk still get a chance, just a small one
+const MIN_CAPACITY_WEIGHT: f64 = 0.1;
+// Nodes not reporting their disk are weighted like a half full one
+const UNKNOWN_CAPACITY_WEIGHT: f64 = 0.5;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionStrategy {
+    /// Random draw weighted by reputation and free disk
+    Weighted,
+    /// Best reputation first
+    Reputation,
+}
+
+/// Regions are free-form names chosen by node owners, such as `eu-west`.
+pub fn validate_region(region: &str) -> Result<(), String> {
+    if region.is_empty()
+        || region.len() > MAX_REGION_LENGTH
+        || !region
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Region \"{}\" must be 1 to {} letters, digits, hyphens or underscores",
+            region, MAX_REGION_LENGTH
+        ));
+    }
+
+    Ok(())
+}
+
+/// What a selector knows about a node, gathered from the database and its last heartbeat.
+#[derive(Debug, Clone)]
+pub struct NodeCandidate {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub region: Option<String>,
+    pub reputation: f64,
+    /// Whether its heartbeat has not expired
+    pub online: bool,
+    /// Free disk reported in its telemetry, if any
+    pub disk_free_bytes: Option<u64>,
+}
+
+/// Where the replicas of a deployment may go.
+#[derive(Debug, Clone, Default)]
+pub struct Placement {
+    /// Size of the deployment, nodes reporting less free disk are left out
+    pub size: u64,
+    /// Regions allowed by the team, any when empty
+    pub regions: Vec<String>,
+    /// Team whose nodes only may be used, if it asked for it
+    pub owner_id: Option<Uuid>,
+}
+
+impl Placement {
+    /// Whether a node can take a replica, regardless of the other replicas.
+    pub fn allows(&self, candidate: &NodeCandidate) -> bool {
+        candidate.online
+            && candidate
+                .disk_free_bytes
+                .is_none_or(|disk_free_bytes| disk_free_bytes >= self.size)
+            && (self.regions.is_empty()
+                || candidate
+                    .region
+                    .as_ref()
+                    .is_some_and(|region| self.regions.contains(region)))
+            && self
+                .owner_id
+                .is_none_or(|owner_id| owner_id == candidate.owner_id)
+    }
+}
+
+/// Chooses the nodes receiving new replicas of a deployment.
+///
+/// Selectors only see the candidates and the nodes already holding a replica, randomness
+/// included, so they can be exercised without a database or a Redis server.
+pub trait NodeSelector: Send + Sync {
+    /// Returns up to `count` candidates allowed by the placement, `placed` holding the nodes
+    /// already pinning the deployment.
+    fn select(
+        &self,
+        candidates: &[NodeCandidate],
+        placed: &[NodeCandidate],
+        count: usize,
+        placement: &Placement,
+        rng: &mut dyn RngCore,
+    ) -> Vec<Uuid>;
+}
+
+pub fn node_selector(strategy: SelectionStrategy) -> Box<dyn NodeSelector> {
+    match strategy {
+        SelectionStrategy::Weighted => Box::new(WeightedSelector),
+        SelectionStrategy::Reputation => Box::new(ReputationSelector),
+    }
+}
+
+/// Draws nodes at random, weighted by reputation and free disk.
+///
+/// Each draw only considers the candidates sharing the fewest owners, then regions, with the
+/// replicas chosen so far, so that a single owner or region going down loses as few as possible.
+pub struct WeightedSelector;
+
+impl NodeSelector for WeightedSelector {
+    fn select(
+        &self,
+        candidates: &[NodeCandidate],
+        placed: &[NodeCandidate],
+        count: usize,
+        placement: &Placement,
+        rng: &mut dyn RngCore,
+    ) -> Vec<Uuid> {
+        let mut remaining = eligible(candidates, placed, placement);
+        let max_free = remaining
+            .iter()
+            .filter_map(|candidate| candidate.disk_free_bytes)
+            .max()
+            .unwrap_or(0);
+        let mut spread = Spread::new(placed);
+
+        let mut selected = Vec::new();
+        while selected.len() < count && !remaining.is_empty() {
+            let best_overlap = remaining
+                .iter()
+                .map(|candidate| spread.overlap(candidate))
+                .min()
+                .unwrap_or(0);
+            let pool: Vec<(usize, f64)> = remaining
+                .iter()
+                .enumerate()
+                .filter(|(_, candidate)| spread.overlap(candidate) == best_overlap)
+                .map(|(index, candidate)| (index, weight(candidate, max_free)))
+                .collect();
+
+            let index = draw(&pool, rng);
+            let candidate = remaining.swap_remove(index);
+            spread.add(candidate);
+            selected.push(candidate.id);
+        }
+
+        selected
+    }
+}
+
+/// Takes the best reputations first, spreading across owners and regions the same way.
+pub struct ReputationSelector;
+
+impl NodeSelector for ReputationSelector {
+    fn select(
+        &self,
+        candidates: &[NodeCandidate],
+        placed: &[NodeCandidate],
+        count: usize,
+        placement: &Placement,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<Uuid> {
+        let mut remaining = eligible(candidates, placed, placement);
+        remaining.sort_by(|a, b| b.reputation.total_cmp(&a.reputation));
+        let mut spread = Spread::new(placed);
+
+        let mut selected = Vec::new();
+        while selected.len() < count && !remaining.is_empty() {
+            // Sorted by reputation, the first one with the least overlap is the best
+            let best_overlap = remaining
+                .iter()
+                .map(|candidate| spread.overlap(candidate))
+                .min()
+                .unwrap_or(0);
+            let index = remaining
+                .iter()
+                .position(|candidate| spread.overlap(candidate) == best_overlap)
+                .unwrap_or(0);
+            let candidate = remaining.remove(index);
+            spread.add(candidate);
+            selected.push(candidate.id);
+        }
+
+        selected
+    }
+}
+
+fn eligible<'a>(
+    candidates: &'a [NodeCandidate],
+    placed: &[NodeCandidate],
+    placement: &Placement,
+) -> Vec<&'a NodeCandidate> {
+    let placed_ids: HashSet<Uuid> = placed.iter().map(|candidate| candidate.id).collect();
+    candidates
+        .iter()
+        .filter(|candidate| !placed_ids.contains(&candidate.id) && placement.allows(candidate))
+        .collect()
+}
+
+/// Owners and regions already holding a replica.
+struct Spread {
+    owners: HashSet<Uuid>,
+    regions: HashSet<String>,
+}
+
+impl Spread {
+    fn new(placed: &[NodeCandidate]) -> Self {
+        let mut spread = Spread {
+            owners: HashSet::new(),
+            regions: HashSet::new(),
+        };
+        for candidate in placed {
+            spread.add(candidate);
+        }
+        spread
+    }
+
+    fn add(&mut self, candidate: &NodeCandidate) {
+        self.owners.insert(candidate.owner_id);
+        if let Some(region) = &candidate.region {
+            self.regions.insert(region.clone());
+        }
+    }
+
+    /// Sharing an owner outweighs sharing a region.
+    fn overlap(&self, candidate: &NodeCandidate) -> u8 {
+        let same_owner = self.owners.contains(&candidate.owner_id);
+        let same_region = candidate
+            .region
+            .as_ref()
+            .is_some_and(|region| self.regions.contains(region));
+        u8::from(same_owner) * 2 + u8::from(same_region)
+    }
+}
+
+fn weight(candidate: &NodeCandidate, max_free: u64) -> f64 {
+    let capacity = match candidate.disk_free_bytes {
+        Some(disk_free_bytes) if max_free > 0 => {
+            (disk_free_bytes as f64 / max_free as f64).max(MIN_CAPACITY_WEIGHT)
+        }
+        Some(_) => MIN_CAPACITY_WEIGHT,
+        None => UNKNOWN_CAPACITY_WEIGHT,
+    };
+    candidate.reputation.max(0.0) * capacity
+}
+
+/// Picks one `(index, weight)` of the pool with a probability proportional to its weight.
+fn draw(pool: &[(usize, f64)], rng: &mut dyn RngCore) -> usize {
+    let total: f64 = pool.iter().map(|(_, weight)| weight).sum();
+    if total <= 0.0 {
+        return pool[rng.random_range(0..pool.len())].0;
+    }
+
+    let mut target = rng.random::<f64>() * total;
+    for (index, weight) in pool {
+        if target < *weight {
+            return *index;
+        }
+        target -= weight;
+    }
+    // Rounding may leave a sliver past the last weight
+    pool[pool.len() - 1].0
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    const SEEDS: u64 = 64;
+
+    fn candidate(owner: u128, region: Option<&str>, reputation: f64) -> NodeCandidate {
+        NodeCandidate {
+            id: Uuid::new_v4(),
+            owner_id: Uuid::from_u128(owner),
+            region: region.map(str::to_string),
+            reputation,
+            online: true,
+            disk_free_bytes: None,
+        }
+    }
+
+    fn selectors() -> [Box<dyn NodeSelector>; 2] {
+        [
+            node_selector(SelectionStrategy::Weighted),
+            node_selector(SelectionStrategy::Reputation),
+        ]
+    }
+
+    #[test]
+    fn leaves_out_nodes_the_placement_does_not_allow() {
+        let team = 1;
+        let eligible = candidate(team, Some("eu-west"), 0.9);
+        let offline = NodeCandidate {
+            online: false,
+            ..candidate(team, Some("eu-west"), 0.9)
+        };
+        let short_on_disk = NodeCandidate {
+            disk_free_bytes: Some(10),
+            ..candidate(team, Some("eu-west"), 0.9)
+        };
+        let outside_regions = candidate(team, Some("us-east"), 0.9);
+        let without_region = candidate(team, None, 0.9);
+        let other_owner = candidate(2, Some("eu-west"), 0.9);
+        let candidates = [
+            offline,
+            short_on_disk,
+            outside_regions,
+            without_region,
+            other_owner,
+            eligible.clone(),
+        ];
+        let placement = Placement {
+            size: 100,
+            regions: vec!["eu-west".to_string()],
+            owner_id: Some(Uuid::from_u128(team)),
+        };
+
+        for selector in selectors() {
+            for seed in 0..SEEDS {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let selected = selector.select(&candidates, &[], 3, &placement, &mut rng);
+                assert_eq!(selected, vec![eligible.id]);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_nodes_with_enough_or_unknown_disk() {
+        let roomy = NodeCandidate {
+            disk_free_bytes: Some(100),
+            ..candidate(1, None, 0.9)
+        };
+        let unknown = candidate(2, None, 0.9);
+        let placement = Placement {
+            size: 100,
+            ..Placement::default()
+        };
+
+        for selector in selectors() {
+            let mut rng = StdRng::seed_from_u64(0);
+            let mut selected = selector.select(
+                &[roomy.clone(), unknown.clone()],
+                &[],
+                2,
+                &placement,
+                &mut rng,
+            );
+            selected.sort();
+            let mut expected = vec![roomy.id, unknown.id];
+            expected.sort();
+            assert_eq!(selected, expected);
+        }
+    }
+
+    #[test]
+    fn spreads_owners_before_regions() {
+        let placed = [candidate(1, Some("eu-west"), 0.9)];
+        // Same owner in another region, another owner in the same region
+        let same_owner = candidate(1, Some("us-east"), 1.0);
+        let same_region = candidate(2, Some("eu-west"), 0.85);
+        let candidates = [same_owner.clone(), same_region.clone()];
+
+        for selector in selectors() {
+            for seed in 0..SEEDS {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let selected =
+                    selector.select(&candidates, &placed, 1, &Placement::default(), &mut rng);
+                assert_eq!(selected, vec![same_region.id]);
+            }
+        }
+    }
+
+    #[test]
+    fn spreads_regions_among_other_owners() {
+        let placed = [candidate(1, Some("eu-west"), 0.9)];
+        let same_region = candidate(2, Some("eu-west"), 1.0);
+        let other_region = candidate(3, Some("us-east"), 0.85);
+        let candidates = [same_region.clone(), other_region.clone()];
+
+        for selector in selectors() {
+            for seed in 0..SEEDS {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let selected =
+                    selector.select(&candidates, &placed, 2, &Placement::default(), &mut rng);
+                assert_eq!(selected, vec![other_region.id, same_region.id]);
+            }
+        }
+    }
+
+    #[test]
+    fn never_selects_placed_nodes() {
+        let placed = candidate(1, None, 1.0);
+        let other = candidate(2, None, 0.9);
+        let candidates = [placed.clone(), other.clone()];
+
+        for selector in selectors() {
+            for seed in 0..SEEDS {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let selected = selector.select(
+                    &candidates,
+                    std::slice::from_ref(&placed),
+                    2,
+                    &Placement::default(),
+                    &mut rng,
+                );
+                assert_eq!(selected, vec![other.id]);
+            }
+        }
+    }
+
+    #[test]
+    fn draws_uniformly_when_every_weight_is_zero() {
+        let pool = [(3, 0.0), (5, 0.0)];
+        let mut drawn = HashSet::new();
+        for seed in 0..SEEDS {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let index = draw(&pool, &mut rng);
+            assert!(index == 3 || index == 5);
+            drawn.insert(index);
+        }
+        assert_eq!(drawn.len(), 2);
+
+        // Nodes with a null reputation are still picked rather than none
+        let candidates = [candidate(1, None, 0.0), candidate(2, None, 0.0)];
+        let mut rng = StdRng::seed_from_u64(0);
+        let selected =
+            WeightedSelector.select(&candidates, &[], 2, &Placement::default(), &mut rng);
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn draws_in_proportion_to_weights() {
+        let pool = [(0, 1.0), (1, 0.0), (2, 3.0)];
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = [0; 3];
+        for _ in 0..4000 {
+            counts[draw(&pool, &mut rng)] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((800..1200).contains(&counts[0]), "{:?}", counts);
+        assert!((2800..3200).contains(&counts[2]), "{:?}", counts);
+    }
+
+    #[test]
+    fn reputation_selector_takes_best_reputations_first() {
+        let good = candidate(1, None, 0.9);
+        let best = candidate(2, None, 0.95);
+        let worst = candidate(3, None, 0.85);
+        let candidates = [good.clone(), best.clone(), worst.clone()];
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let selected =
+            ReputationSelector.select(&candidates, &[], 3, &Placement::default(), &mut rng);
+        assert_eq!(selected, vec![best.id, good.id, worst.id]);
+
+        let selected =
+            ReputationSelector.select(&candidates, &[], 2, &Placement::default(), &mut rng);
+        assert_eq!(selected, vec![best.id, good.id]);
+    }
+}
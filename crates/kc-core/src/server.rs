@@ -1,19 +1,33 @@
 use serde::Deserialize;
 
 use crate::{
-    app::AppRegistry,
+    access::AccessConfig,
+    app::{AppRegistry, DomainRegistry},
     database::{DatabaseConfig, DbPool},
+    deploy::DeployConfig,
+    domain::DomainConfig,
+    events::{EventBus, NodeEventBus},
+    gateway::GatewayConfig,
+    ipfs::IpfsClient,
+    jobs::JobsConfig,
     models::query::AppSchema,
-    node::NodeHealthConfig,
+    node::{NodeAuthConfig, NodeHealthConfig},
     redis::{RedisClient, RedisSettings},
+    replication::ReplicationConfig,
+    reputation::ReputationConfig,
+    retention::RetentionConfig,
 };
 
 #[derive(Clone)]
 pub struct ServerState {
     pub server_settings: ServerSettings,
     pub app_registry: AppRegistry,
+    pub domain_registry: DomainRegistry,
     pub db_pool: DbPool,
     pub redis_client: RedisClient,
+    pub ipfs: IpfsClient,
+    pub event_bus: EventBus,
+    pub node_event_bus: NodeEventBus,
     pub graphql_schema: AppSchema,
 }
 
@@ -23,13 +37,23 @@ pub struct ServerConfig {
     pub host: String,
     pub peer_id: String,
     pub ipfs_host: String,
+    pub ipfs_backend: String,
     pub jwt_secret: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerSettings {
     pub server: ServerConfig,
+    pub deploy: DeployConfig,
+    pub jobs: JobsConfig,
+    pub gateway: GatewayConfig,
+    pub domains: DomainConfig,
+    pub access: AccessConfig,
+    pub replication: ReplicationConfig,
+    pub retention: RetentionConfig,
     pub node_health: NodeHealthConfig,
+    pub node_auth: NodeAuthConfig,
+    pub reputation: ReputationConfig,
     pub database: DatabaseConfig,
     pub redis: RedisSettings,
 }
@@ -0,0 +1,350 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+pub const REDIRECTS_FILE: &str = "_redirects";
+pub const HEADERS_FILE: &str = "_headers";
+pub const CONFIG_FILE: &str = "keyston.toml";
+// Served with a 404 status when no file nor rule matches, unless the site config names another
+const DEFAULT_NOT_FOUND_PAGE: &str = "404.html";
+// Past this many rules, a site config is more likely a mistake than a need
+const MAX_RULES: usize = 1000;
+// 200 rewrites the path, 404 serves the target as the not found page
+const RULE_STATUSES: [u16; 7] = [200, 301, 302, 303, 307, 308, 404];
+
+/// Redirects, rewrites and headers of a site, compiled from its config files at deploy time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SiteRules {
+    #[serde(default)]
+    pub redirects: Vec<RedirectRule>,
+    #[serde(default)]
+    pub headers: Vec<HeaderRule>,
+    pub not_found_page: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedirectRule {
+    pub from: String,
+    pub to: String,
+    #[serde(default = "default_status")]
+    pub status: u16,
+    /// Applies even when a file exists at the path
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderRule {
+    #[serde(rename = "for")]
+    pub path: String,
+    pub values: BTreeMap<String, String>,
+}
+
+/// A redirect rule matching a request path, with its placeholders filled in.
+pub struct RuleMatch<'a> {
+    pub rule: &'a RedirectRule,
+    pub target: String,
+}
+
+fn default_status() -> u16 {
+    301
+}
+
+impl SiteRules {
+    pub fn is_empty(&self) -> bool {
+        self.redirects.is_empty() && self.headers.is_empty() && self.not_found_page.is_none()
+    }
+
+    /// Finds the first redirect rule matching the path, among forced or shadowed rules.
+    pub fn find_redirect(&self, path: &str, forced: bool) -> Option<RuleMatch<'_>> {
+        self.redirects
+            .iter()
+            .filter(|rule| rule.force == forced)
+            .find_map(|rule| {
+                match_pattern(&rule.from, path).map(|params| RuleMatch {
+                    rule,
+                    target: fill_placeholders(&rule.to, &params),
+                })
+            })
+    }
+
+    /// Returns the custom headers of a path, later rules overriding earlier ones.
+    pub fn headers_for(&self, path: &str) -> BTreeMap<&str, &str> {
+        self.headers
+            .iter()
+            .filter(|rule| match_pattern(&rule.path, path).is_some())
+            .flat_map(|rule| rule.values.iter())
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+}
+
+/// Reads and validates the `_redirects`, `_headers` and `keyston.toml` files of a site directory.
+pub async fn load_site_rules(site_dir: &Path) -> Result<SiteRules, String> {
+    let mut rules = match read_site_file(site_dir, CONFIG_FILE).await? {
+        Some(content) => toml::from_str::<SiteRules>(&content)
+            .map_err(|e| format!("Invalid {}: {}", CONFIG_FILE, e))?,
+        None => SiteRules::default(),
+    };
+
+    // Rules of the dedicated files come first, as they are the most specific
+    if let Some(content) = read_site_file(site_dir, REDIRECTS_FILE).await? {
+        let mut redirects = parse_redirects(&content)?;
+        redirects.append(&mut rules.redirects);
+        rules.redirects = redirects;
+    }
+    if let Some(content) = read_site_file(site_dir, HEADERS_FILE).await? {
+        let mut headers = parse_headers(&content)?;
+        headers.append(&mut rules.headers);
+        rules.headers = headers;
+    }
+    if rules.not_found_page.is_none()
+        && fs::try_exists(site_dir.join(DEFAULT_NOT_FOUND_PAGE))
+            .await
+            .unwrap_or(false)
+    {
+        rules.not_found_page = Some(format!("/{}", DEFAULT_NOT_FOUND_PAGE));
+    }
+
+    validate_site_rules(&rules)?;
+    Ok(rules)
+}
+
+async fn read_site_file(site_dir: &Path, name: &str) -> Result<Option<String>, String> {
+    match fs::read_to_string(site_dir.join(name)).await {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Unable to read {}: {}", name, e)),
+    }
+}
+
+/// Parses `_redirects` lines of the form `from to [status][!]`.
+pub fn parse_redirects(content: &str) -> Result<Vec<RedirectRule>, String> {
+    let mut redirects = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let (from, to, status) = match fields.as_slice() {
+            [from, to] => (*from, *to, None),
+            [from, to, status] => (*from, *to, Some(*status)),
+            _ => {
+                return Err(format!(
+                    "{} line {}: expected \"from to [status]\"",
+                    REDIRECTS_FILE,
+                    index + 1
+                ));
+            }
+        };
+
+        let (status, force) = match status {
+            Some(status) => {
+                let force = status.ends_with('!');
+                let status = status.trim_end_matches('!').parse::<u16>().map_err(|_| {
+                    format!(
+                        "{} line {}: invalid status \"{}\"",
+                        REDIRECTS_FILE,
+                        index + 1,
+                        status
+                    )
+                })?;
+                (status, force)
+            }
+            None => (default_status(), false),
+        };
+
+        redirects.push(RedirectRule {
+            from: from.to_string(),
+            to: to.to_string(),
+            status,
+            force,
+        });
+    }
+
+    Ok(redirects)
+}
+
+/// Parses `_headers` blocks: a path pattern followed by indented `Name: value` lines.
+pub fn parse_headers(content: &str) -> Result<Vec<HeaderRule>, String> {
+    let mut headers: Vec<HeaderRule> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) {
+            headers.push(HeaderRule {
+                path: line.trim().to_string(),
+                values: BTreeMap::new(),
+            });
+            continue;
+        }
+
+        let rule = headers.last_mut().ok_or_else(|| {
+            format!(
+                "{} line {}: header without a path above it",
+                HEADERS_FILE,
+                index + 1
+            )
+        })?;
+        let (name, value) = line.trim().split_once(':').ok_or_else(|| {
+            format!(
+                "{} line {}: expected \"Name: value\"",
+                HEADERS_FILE,
+                index + 1
+            )
+        })?;
+
+        // Repeated headers are folded into one list
+        rule.values
+            .entry(name.trim().to_ascii_lowercase())
+            .and_modify(|values| {
+                values.push_str(", ");
+                values.push_str(value.trim());
+            })
+            .or_insert_with(|| value.trim().to_string());
+    }
+
+    Ok(headers)
+}
+
+pub fn validate_site_rules(rules: &SiteRules) -> Result<(), String> {
+    if rules.redirects.len() + rules.headers.len() > MAX_RULES {
+        return Err(format!("Sites cannot have more than {} rules", MAX_RULES));
+    }
+
+    for rule in &rules.redirects {
+        validate_pattern(&rule.from)?;
+        if !RULE_STATUSES.contains(&rule.status) {
+            return Err(format!(
+                "Unsupported status {} for rule {}",
+                rule.status, rule.from
+            ));
+        }
+
+        let external = rule.to.starts_with("http://") || rule.to.starts_with("https://");
+        if !external && !rule.to.starts_with('/') {
+            return Err(format!(
+                "Target {} of rule {} must be a site path or an URL",
+                rule.to, rule.from
+            ));
+        }
+        if external && matches!(rule.status, 200 | 404) {
+            return Err(format!(
+                "Rule {} can only rewrite to a path of the site",
+                rule.from
+            ));
+        }
+
+        let placeholders = pattern_placeholders(&rule.from);
+        if let Some(placeholder) = rule
+            .to
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix(':'))
+            .find(|name| !placeholders.contains(name))
+        {
+            return Err(format!(
+                "Placeholder :{} of rule {} is not in its source path",
+                placeholder, rule.from
+            ));
+        }
+    }
+
+    for rule in &rules.headers {
+        validate_pattern(&rule.path)?;
+        for (name, value) in &rule.values {
+            if HeaderName::from_bytes(name.as_bytes()).is_err()
+                || HeaderValue::from_str(value).is_err()
+            {
+                return Err(format!("Invalid header {} for {}", name, rule.path));
+            }
+        }
+    }
+
+    if let Some(page) = &rules.not_found_page
+        && !page.starts_with('/')
+    {
+        return Err(format!("Not found page {} must be a site path", page));
+    }
+
+    Ok(())
+}
+
+fn validate_pattern(pattern: &str) -> Result<(), String> {
+    if !pattern.starts_with('/') {
+        return Err(format!("Path {} must start with /", pattern));
+    }
+
+    let segments = split_segments(pattern);
+    if let Some(position) = segments.iter().position(|segment| *segment == "*")
+        && position + 1 != segments.len()
+    {
+        return Err(format!("Splat of {} must end the path", pattern));
+    }
+
+    Ok(())
+}
+
+fn split_segments(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+fn pattern_placeholders(pattern: &str) -> Vec<&str> {
+    split_segments(pattern)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            "*" => Some("splat"),
+            _ => segment.strip_prefix(':'),
+        })
+        .collect()
+}
+
+/// Matches a path against a pattern, returning the values of its placeholders and splat.
+fn match_pattern(pattern: &str, path: &str) -> Option<Vec<(String, String)>> {
+    let pattern_segments = split_segments(pattern);
+    let path_segments = split_segments(path);
+    let mut params = Vec::new();
+
+    for (index, segment) in pattern_segments.iter().enumerate() {
+        if *segment == "*" {
+            params.push((
+                "splat".to_string(),
+                path_segments[index.min(path_segments.len())..].join("/"),
+            ));
+            return Some(params);
+        }
+
+        let value = path_segments.get(index)?;
+        match segment.strip_prefix(':') {
+            Some(name) => params.push((name.to_string(), value.to_string())),
+            None if segment == value => {}
+            None => return None,
+        }
+    }
+
+    (pattern_segments.len() == path_segments.len()).then_some(params)
+}
+
+fn fill_placeholders(target: &str, params: &[(String, String)]) -> String {
+    target
+        .split('/')
+        .map(|segment| {
+            segment
+                .strip_prefix(':')
+                .and_then(|name| params.iter().find(|(param, _)| param == name))
+                .map(|(_, value)| value.as_str())
+                .unwrap_or(segment)
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,197 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use thiserror::Error;
+use tokio::task;
+
+use crate::deploy::DeployConfig;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("Unsupported archive format (expected tar, tar.gz or zip)")]
+    UnsupportedFormat,
+    #[error("Archive is larger than {0} bytes")]
+    ArchiveTooLarge(u64),
+    #[error("Archive content is larger than {0} bytes once unpacked")]
+    UnpackedTooLarge(u64),
+    #[error("Archive contains more than {0} files")]
+    TooManyFiles(u64),
+    #[error("Archive entry \"{0}\" points outside of the site root")]
+    UnsafePath(String),
+    #[error("Archive read error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid zip archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Archive thread error")]
+    TaskFailed(#[from] task::JoinError),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnpackSummary {
+    pub file_count: u64,
+    pub total_size: u64,
+}
+
+enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+struct Unpacker<'a> {
+    dest: &'a Path,
+    config: &'a DeployConfig,
+    summary: UnpackSummary,
+}
+
+impl Unpacker<'_> {
+    fn create_dir(&self, path: &Path) -> Result<(), ArchiveError> {
+        fs::create_dir_all(self.dest.join(path))?;
+        Ok(())
+    }
+
+    fn write_file(&mut self, path: &Path, reader: &mut impl Read) -> Result<(), ArchiveError> {
+        self.summary.file_count += 1;
+        if self.summary.file_count > self.config.max_files {
+            return Err(ArchiveError::TooManyFiles(self.config.max_files));
+        }
+
+        let target = self.dest.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Headers can lie about entry sizes, so count what is actually written.
+        let remaining = self.config.max_unpacked_size - self.summary.total_size;
+        let mut file = File::create(&target)?;
+        let written = io::copy(&mut reader.take(remaining + 1), &mut file)?;
+        if written > remaining {
+            return Err(ArchiveError::UnpackedTooLarge(
+                self.config.max_unpacked_size,
+            ));
+        }
+
+        self.summary.total_size += written;
+        Ok(())
+    }
+}
+
+pub async fn unpack_archive(
+    archive_path: PathBuf,
+    dest: PathBuf,
+    config: DeployConfig,
+) -> Result<UnpackSummary, ArchiveError> {
+    let summary = task::spawn_blocking(move || unpack(&archive_path, &dest, &config)).await??;
+
+    Ok(summary)
+}
+
+fn unpack(
+    archive_path: &Path,
+    dest: &Path,
+    config: &DeployConfig,
+) -> Result<UnpackSummary, ArchiveError> {
+    let mut file = File::open(archive_path)?;
+    if file.metadata()?.len() > config.max_archive_size {
+        return Err(ArchiveError::ArchiveTooLarge(config.max_archive_size));
+    }
+
+    fs::create_dir_all(dest)?;
+    let mut unpacker = Unpacker {
+        dest,
+        config,
+        summary: UnpackSummary::default(),
+    };
+
+    match detect_format(&mut file)? {
+        ArchiveFormat::Tar => unpack_tar(file, &mut unpacker)?,
+        ArchiveFormat::TarGz => unpack_tar(GzDecoder::new(file), &mut unpacker)?,
+        ArchiveFormat::Zip => unpack_zip(file, &mut unpacker)?,
+    }
+
+    Ok(unpacker.summary)
+}
+
+fn detect_format(file: &mut File) -> Result<ArchiveFormat, ArchiveError> {
+    let mut header = Vec::with_capacity(262);
+    file.by_ref().take(262).read_to_end(&mut header)?;
+    file.rewind()?;
+
+    if header.starts_with(b"PK\x03\x04") {
+        Ok(ArchiveFormat::Zip)
+    } else if header.starts_with(&[0x1f, 0x8b]) {
+        Ok(ArchiveFormat::TarGz)
+    } else if header.len() == 262 && &header[257..262] == b"ustar" {
+        Ok(ArchiveFormat::Tar)
+    } else {
+        Err(ArchiveError::UnsupportedFormat)
+    }
+}
+
+fn unpack_tar(reader: impl Read, unpacker: &mut Unpacker) -> Result<(), ArchiveError> {
+    let mut archive = tar::Archive::new(reader);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = sanitize_path(&entry.path()?)?;
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => unpacker.create_dir(&path)?,
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                unpacker.write_file(&path, &mut entry)?
+            }
+            tar::EntryType::Symlink | tar::EntryType::Link => {
+                return Err(ArchiveError::UnsafePath(path.display().to_string()));
+            }
+            _ => continue,
+        }
+    }
+
+    Ok(())
+}
+
+fn unpack_zip(file: File, unpacker: &mut Unpacker) -> Result<(), ArchiveError> {
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let path = match entry.enclosed_name() {
+            Some(path) => sanitize_path(&path)?,
+            None => return Err(ArchiveError::UnsafePath(entry.name().to_string())),
+        };
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+
+        if entry.is_symlink() {
+            return Err(ArchiveError::UnsafePath(path.display().to_string()));
+        } else if entry.is_dir() {
+            unpacker.create_dir(&path)?;
+        } else {
+            unpacker.write_file(&path, &mut entry)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn sanitize_path(path: &Path) -> Result<PathBuf, ArchiveError> {
+    let mut clean = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => clean.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(ArchiveError::UnsafePath(path.display().to_string()));
+            }
+        }
+    }
+
+    Ok(clean)
+}
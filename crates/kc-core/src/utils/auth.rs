@@ -40,3 +40,18 @@ pub async fn verify_password(password: String, hash_str: String) -> Result<bool,
 
     Ok(is_valid)
 }
+
+/// Compares two secrets without leaking the position of the first difference.
+pub fn secrets_match(expected: &str, provided: &str) -> bool {
+    let expected = expected.as_bytes();
+    let provided = provided.as_bytes();
+    if expected.len() != provided.len() {
+        return false;
+    }
+
+    expected
+        .iter()
+        .zip(provided)
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
@@ -0,0 +1,169 @@
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use axum_extra::headers::{
+    Authorization, Cookie, HeaderMapExt,
+    authorization::{Basic, Bearer},
+};
+use percent_encoding::percent_decode_str;
+
+use kc_core::{
+    access::{
+        AccessScope, AppAccess, issue_access_token, session_cookie_name, verify_access_token,
+    },
+    authentication::decode_claims,
+    models::{app::AccessMode, team::Team},
+    server::ServerState,
+    utils::auth::verify_password,
+};
+
+/// Query parameter carrying signed links, or short-lived sessions of team members
+const ACCESS_TOKEN_PARAMETER: &str = "access_token";
+
+/// Cache-Control of private apps, which shared caches must not keep
+pub const PRIVATE_CACHE_CONTROL: &str = "private, no-cache";
+
+/// Lets a visitor in an app, returning the session cookie to set after a first authentication.
+///
+/// A valid session cookie is enough, otherwise the visitor authenticates the way the access
+/// mode of the app asks for.
+pub async fn authorize(
+    state: &ServerState,
+    app_name: &str,
+    access: &AppAccess,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Result<Option<HeaderValue>, Response> {
+    if access.mode == AccessMode::PUBLIC {
+        return Ok(None);
+    }
+
+    let jwt_secret = &state.server_settings.server.jwt_secret;
+    let cookie_name = session_cookie_name(&access.app_id);
+    if let Some(cookie) = headers.typed_get::<Cookie>()
+        && let Some(token) = cookie.get(&cookie_name)
+        && verify_access_token(jwt_secret, access, AccessScope::Session, token)
+    {
+        return Ok(None);
+    }
+
+    let authenticated = match access.mode {
+        AccessMode::PUBLIC => true,
+        AccessMode::PASSWORD => password_matches(app_name, access, headers).await,
+        AccessMode::TOKEN => query_token(uri).is_some_and(|token| {
+            verify_access_token(jwt_secret, access, AccessScope::Link, &token)
+        }),
+        AccessMode::TEAM => {
+            query_token(uri).is_some_and(|token| {
+                verify_access_token(jwt_secret, access, AccessScope::Session, &token)
+            }) || is_team_member(state, access, headers).await
+        }
+    };
+    if !authenticated {
+        println!(
+            "[WEB] Access to app \"{}\" denied ({})",
+            app_name,
+            access.mode.name()
+        );
+        return Err(denied_response(app_name, access.mode));
+    }
+
+    let ttl = state.server_settings.access.session_ttl_seconds;
+    let token = match issue_access_token(jwt_secret, access, AccessScope::Session, ttl) {
+        Ok((token, _)) => token,
+        Err(e) => {
+            eprintln!(
+                "[WEB] Error in session creation for app \"{}\": {}",
+                app_name, e
+            );
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "Session error").into_response());
+        }
+    };
+
+    let mut cookie = format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+        cookie_name, token, ttl
+    );
+    if state.server_settings.access.secure_cookies {
+        cookie.push_str("; Secure");
+    }
+    // Tokens are base64url, always valid in a header
+    Ok(HeaderValue::from_str(&cookie).ok())
+}
+
+async fn password_matches(app_name: &str, access: &AppAccess, headers: &HeaderMap) -> bool {
+    let (Some(Authorization(basic)), Some(password_hash)) = (
+        headers.typed_get::<Authorization<Basic>>(),
+        access.password_hash.clone(),
+    ) else {
+        return false;
+    };
+
+    match verify_password(basic.password().to_string(), password_hash).await {
+        Ok(matches) => matches,
+        Err(e) => {
+            eprintln!(
+                "[WEB] Error in password verification for app \"{}\": {}",
+                app_name, e
+            );
+            false
+        }
+    }
+}
+
+/// Team members sign in with their user token, only accepted in the Authorization header so
+/// that it never ends up in URLs.
+async fn is_team_member(state: &ServerState, access: &AppAccess, headers: &HeaderMap) -> bool {
+    let Some(Authorization(bearer)) = headers.typed_get::<Authorization<Bearer>>() else {
+        return false;
+    };
+    let Some(claims) = decode_claims(&state.server_settings.server.jwt_secret, bearer.token())
+    else {
+        return false;
+    };
+
+    match Team::is_managed_by(&state.db_pool, &access.team_id, &claims).await {
+        Ok(is_member) => is_member,
+        Err(e) => {
+            eprintln!("[WEB] Error in team membership check: {}", e);
+            false
+        }
+    }
+}
+
+fn query_token(uri: &Uri) -> Option<String> {
+    uri.query()?
+        .split('&')
+        .filter_map(|parameter| parameter.split_once('='))
+        .find(|(name, _)| *name == ACCESS_TOKEN_PARAMETER)
+        .and_then(|(_, value)| percent_decode_str(value).decode_utf8().ok())
+        .map(|value| value.into_owned())
+}
+
+fn denied_response(app_name: &str, mode: AccessMode) -> Response {
+    let mut response = match mode {
+        AccessMode::PASSWORD => (StatusCode::UNAUTHORIZED, "Password required").into_response(),
+        AccessMode::TOKEN => {
+            (StatusCode::UNAUTHORIZED, "Valid access link required").into_response()
+        }
+        AccessMode::PUBLIC | AccessMode::TEAM => {
+            (StatusCode::UNAUTHORIZED, "Team sign in required").into_response()
+        }
+    };
+
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    if mode == AccessMode::PASSWORD {
+        // Browsers prompt for the password, naming the app
+        let challenge = format!(
+            "Basic realm=\"{}\", charset=\"UTF-8\"",
+            app_name.replace('"', "")
+        );
+        if let Ok(challenge) = HeaderValue::from_str(&challenge) {
+            response_headers.insert(header::WWW_AUTHENTICATE, challenge);
+        }
+    }
+
+    response
+}
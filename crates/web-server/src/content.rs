@@ -0,0 +1,351 @@
+use kc_core::ipfs::{IpfsBackend, IpfsEntry, IpfsError};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use sha2::{Digest, Sha256};
+
+// How much of a file is looked at when its extension says nothing about its type
+pub const SNIFF_LENGTH: usize = 512;
+
+const PATH_SEGMENT_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+pub struct SiteFile {
+    pub ipfs_path: String,
+    pub name: String,
+    pub size: u64,
+    /// CIDs of the site root and of every entry down to the file
+    pub roots: Vec<String>,
+}
+
+/// What a request path points to inside a site CID.
+pub enum SiteTarget {
+    File(SiteFile),
+    Directory {
+        ipfs_path: String,
+        roots: Vec<String>,
+    },
+}
+
+/// Byte range to serve, from a `Range` request header.
+pub enum ByteRange {
+    Full,
+    /// Inclusive bounds, as in `Content-Range`
+    Partial {
+        start: u64,
+        end: u64,
+    },
+    Unsatisfiable,
+}
+
+/// Splits a request path into segments, refusing anything that climbs out of the site.
+pub fn path_segments(path: &str) -> Option<Vec<&str>> {
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => return None,
+            _ if segment.contains('\\') => return None,
+            _ => segments.push(segment),
+        }
+    }
+
+    Some(segments)
+}
+
+/// Finds whether the path names a file or a directory of the site.
+pub async fn resolve_site_path(
+    ipfs: &dyn IpfsBackend,
+    cid: &str,
+    segments: &[&str],
+) -> Result<SiteTarget, IpfsError> {
+    let mut ipfs_path = cid.to_string();
+    let mut roots = vec![cid.to_string()];
+
+    // Sites deployed before archives were a single HTML file rather than a directory
+    if segments.is_empty() {
+        let root = ipfs.stat(cid).await?;
+        if !root.is_dir {
+            return Ok(SiteTarget::File(SiteFile {
+                ipfs_path,
+                name: "index.html".to_string(),
+                size: root.size,
+                roots,
+            }));
+        }
+    }
+
+    for (index, name) in segments.iter().enumerate() {
+        let entry = find_entry(ipfs, &ipfs_path, name).await?;
+        ipfs_path = format!("{}/{}", ipfs_path, name);
+        roots.push(entry.cid);
+
+        if !entry.is_dir {
+            // A file cannot have children
+            if index + 1 < segments.len() {
+                return Err(IpfsError::NotFound(format!(
+                    "{}/{}",
+                    cid,
+                    segments.join("/")
+                )));
+            }
+            return Ok(SiteTarget::File(SiteFile {
+                ipfs_path,
+                name: entry.name,
+                size: entry.size,
+                roots,
+            }));
+        }
+    }
+
+    Ok(SiteTarget::Directory { ipfs_path, roots })
+}
+
+/// Looks up a file directly inside a directory.
+pub async fn find_file(
+    ipfs: &dyn IpfsBackend,
+    dir_path: &str,
+    dir_roots: &[String],
+    name: &str,
+) -> Result<SiteFile, IpfsError> {
+    let entry = find_entry(ipfs, dir_path, name).await?;
+    if entry.is_dir {
+        return Err(IpfsError::NotFound(format!("{}/{}", dir_path, name)));
+    }
+
+    let mut roots = dir_roots.to_vec();
+    roots.push(entry.cid);
+    Ok(SiteFile {
+        ipfs_path: format!("{}/{}", dir_path, name),
+        name: entry.name,
+        size: entry.size,
+        roots,
+    })
+}
+
+async fn find_entry(
+    ipfs: &dyn IpfsBackend,
+    dir_path: &str,
+    name: &str,
+) -> Result<IpfsEntry, IpfsError> {
+    ipfs.ls(dir_path)
+        .await?
+        .into_iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| IpfsError::NotFound(format!("{}/{}", dir_path, name)))
+}
+
+/// Content path of a site path, as sent in `X-Ipfs-Path`.
+pub fn content_path(root: &str, segments: &[&str]) -> String {
+    let mut path = root.to_string();
+    for segment in segments {
+        path.push('/');
+        path.extend(utf8_percent_encode(segment, PATH_SEGMENT_ENCODE_SET));
+    }
+
+    path
+}
+
+/// Strong entity tag of a site path, content under a CID never changes.
+pub fn entity_tag(cid: &str, segments: &[&str]) -> String {
+    let digest = Sha256::digest(format!("{}/{}", cid, segments.join("/")));
+    format!("\"{}\"", hex::encode(digest))
+}
+
+/// Checks an `If-None-Match` header value against an entity tag, with weak comparison.
+///
+/// The `*` wildcard is not honored, the gateway only answers GET and HEAD requests and would
+/// otherwise report paths as unmodified before knowing whether they exist.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag.trim_start_matches("W/") == etag)
+}
+
+/// Reads a single `bytes=` range, multiple ranges fall back to the whole file.
+pub fn parse_range(value: &str, size: u64) -> ByteRange {
+    let spec = match value.trim().strip_prefix("bytes=") {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return ByteRange::Full,
+    };
+    let (first, last) = match spec.split_once('-') {
+        Some(bounds) => bounds,
+        None => return ByteRange::Full,
+    };
+
+    let (start, end) = match (first.trim(), last.trim()) {
+        // Suffix range: the last N bytes
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Ok(length) => (size.saturating_sub(length), size.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, size.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(size.saturating_sub(1))),
+            _ => return ByteRange::Full,
+        },
+    };
+
+    if size == 0 || start >= size {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial { start, end }
+}
+
+/// Guesses the content type from the file extension.
+pub fn guess_content_type(file_name: &str) -> Option<String> {
+    let mime = mime_guess::from_path(file_name).first()?;
+    let essence = mime.essence_str();
+    if mime.type_() == mime_guess::mime::TEXT || essence == "application/javascript" {
+        return Some(format!("{}; charset=utf-8", essence));
+    }
+
+    Some(essence.to_string())
+}
+
+/// Guesses the content type from the first bytes of a file.
+pub fn sniff_content_type(head: &[u8]) -> String {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"\x00asm", "application/wasm"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+    ];
+    if let Some((_, mime)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| head.starts_with(signature))
+    {
+        return mime.to_string();
+    }
+
+    match std::str::from_utf8(head) {
+        Ok(text) => {
+            let start = text.trim_start().to_ascii_lowercase();
+            if start.starts_with("<!doctype html") || start.starts_with("<html") {
+                "text/html; charset=utf-8".to_string()
+            } else {
+                "text/plain; charset=utf-8".to_string()
+            }
+        }
+        // A multi-byte character cut at the end of the sample is still text
+        Err(e) if e.error_len().is_none() => "text/plain; charset=utf-8".to_string(),
+        Err(_) => "application/octet-stream".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kc_core::ipfs::MemoryBackend;
+
+    use super::*;
+
+    async fn add_site(ipfs: &MemoryBackend) -> String {
+        let site_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(site_dir.path().join("blog/2024")).unwrap();
+        std::fs::write(site_dir.path().join("index.html"), "<h1>Home</h1>").unwrap();
+        std::fs::write(site_dir.path().join("blog/index.html"), "<h1>Blog</h1>").unwrap();
+        std::fs::write(site_dir.path().join("blog/2024/post.html"), "<p>Post</p>").unwrap();
+
+        ipfs.add(site_dir.path()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn resolves_files_with_the_roots_down_to_them() {
+        let ipfs = MemoryBackend::new();
+        let cid = add_site(&ipfs).await;
+
+        let file = match resolve_site_path(&ipfs, &cid, &["blog", "2024", "post.html"]).await {
+            Ok(SiteTarget::File(file)) => file,
+            _ => panic!("post.html should resolve to a file"),
+        };
+        assert_eq!(file.ipfs_path, format!("{}/blog/2024/post.html", cid));
+        assert_eq!((file.name.as_str(), file.size), ("post.html", 11));
+        assert_eq!(file.roots.len(), 4);
+        assert_eq!(file.roots[0], cid);
+
+        let directory = ipfs.ls(&format!("{}/blog/2024", cid)).await.unwrap();
+        assert_eq!(file.roots[3], directory[0].cid);
+    }
+
+    #[tokio::test]
+    async fn resolves_directories_to_their_index() {
+        let ipfs = MemoryBackend::new();
+        let cid = add_site(&ipfs).await;
+
+        let (ipfs_path, roots) = match resolve_site_path(&ipfs, &cid, &["blog"]).await {
+            Ok(SiteTarget::Directory { ipfs_path, roots }) => (ipfs_path, roots),
+            _ => panic!("blog should resolve to a directory"),
+        };
+        assert_eq!(ipfs_path, format!("{}/blog", cid));
+        assert_eq!(roots.len(), 2);
+
+        let index = find_file(&ipfs, &ipfs_path, &roots, "index.html")
+            .await
+            .unwrap();
+        assert_eq!(index.ipfs_path, format!("{}/blog/index.html", cid));
+        assert_eq!(index.roots.len(), 3);
+
+        // The root of the site is a directory too
+        assert!(matches!(
+            resolve_site_path(&ipfs, &cid, &[]).await,
+            Ok(SiteTarget::Directory { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn serves_a_single_file_root_as_the_site_index() {
+        let ipfs = MemoryBackend::new();
+        let cid = add_site(&ipfs).await;
+        let file_cid = ipfs.stat(&format!("{}/index.html", cid)).await.unwrap().cid;
+
+        let file = match resolve_site_path(&ipfs, &file_cid, &[]).await {
+            Ok(SiteTarget::File(file)) => file,
+            _ => panic!("a file root should resolve to a file"),
+        };
+        assert_eq!(file.ipfs_path, file_cid);
+        assert_eq!((file.name.as_str(), file.size), ("index.html", 13));
+        assert_eq!(file.roots, vec![file_cid.clone()]);
+
+        assert!(matches!(
+            resolve_site_path(&ipfs, &file_cid, &["about.html"]).await,
+            Err(IpfsError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reports_missing_paths_as_not_found() {
+        let ipfs = MemoryBackend::new();
+        let cid = add_site(&ipfs).await;
+
+        assert!(matches!(
+            resolve_site_path(&ipfs, &cid, &["missing.html"]).await,
+            Err(IpfsError::NotFound(_))
+        ));
+        // A file has no children
+        assert!(matches!(
+            resolve_site_path(&ipfs, &cid, &["index.html", "child"]).await,
+            Err(IpfsError::NotFound(_))
+        ));
+        // Only files are looked up in a directory, not subdirectories
+        assert!(matches!(
+            find_file(&ipfs, &cid, std::slice::from_ref(&cid), "blog").await,
+            Err(IpfsError::NotFound(_))
+        ));
+        assert!(matches!(
+            find_file(&ipfs, &format!("{}/blog/2024", cid), &[], "index.html").await,
+            Err(IpfsError::NotFound(_))
+        ));
+    }
+}
@@ -1,10 +1,29 @@
 use axum::{Router, routing::get};
 use kc_core::server::ServerState;
 
+pub mod access;
+pub mod content;
 pub mod routes;
 
 pub fn create_router() -> Router<ServerState> {
     Router::new()
         .route("/app/{app_name}", get(routes::gateway::web_handler))
+        .route("/app/{app_name}/", get(routes::gateway::web_handler))
+        .route("/app/{app_name}/{*path}", get(routes::gateway::web_handler))
+        .route(
+            "/preview/{deployment_id}",
+            get(routes::gateway::preview_handler),
+        )
+        .route(
+            "/preview/{deployment_id}/",
+            get(routes::gateway::preview_handler),
+        )
+        .route(
+            "/preview/{deployment_id}/{*path}",
+            get(routes::gateway::preview_handler),
+        )
+        .route("/ipfs/{cid}", get(routes::trustless::ipfs_handler))
+        .route("/ipfs/{cid}/", get(routes::gateway::cid_handler))
+        .route("/ipfs/{cid}/{*path}", get(routes::gateway::cid_handler))
         .fallback(routes::app::fallback)
 }
@@ -1,83 +1,695 @@
 use axum::{
     body::Body,
-    extract::{Path, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    extract::{OriginalUri, Path, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri, header},
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
 };
-use kc_core::{models::app::App, server::ServerState};
-use reqwest::Client;
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use serde::Deserialize;
+
+use crate::access::{PRIVATE_CACHE_CONTROL, authorize};
+use crate::content::{
+    ByteRange, SNIFF_LENGTH, SiteFile, SiteTarget, content_path, entity_tag, etag_matches,
+    find_file, guess_content_type, parse_range, path_segments, resolve_site_path,
+    sniff_content_type,
+};
+use kc_core::{
+    access::AppAccess,
+    app::{AppResolveError, resolve_app, resolve_host},
+    deploy::{find_deployment_by_reference, is_deployment_id},
+    domain::{host_name, is_subdomain_label, subdomain_label},
+    ipfs::{IpfsBackend, IpfsError, cat_to_vec},
+    models::{
+        app::{AccessMode, App},
+        deployment::Deployment,
+    },
+    server::ServerState,
+    site_rules::{RuleMatch, SiteRules},
+};
+
+pub(crate) const X_IPFS_PATH: &str = "x-ipfs-path";
+const X_IPFS_ROOTS: &str = "x-ipfs-roots";
+
+// Rule targets are decoded paths or URLs, only what cannot go in a header is escaped
+const LOCATION_ENCODE_SET: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'<').add(b'>');
+
+/// Where a served site comes from, which decides how long its responses can be cached.
+struct SiteRoot<'a> {
+    cid: &'a str,
+    /// `/ipns/{name}` or `/ipfs/{cid}`
+    content_root: String,
+    cache_control: &'a str,
+    /// Where the site root is mounted on the gateway, empty on subdomains and custom domains
+    base_path: String,
+    rules: Option<&'a SiteRules>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AppPath {
+    app_name: String,
+    path: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PreviewPath {
+    deployment_id: String,
+    path: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CidPath {
+    cid: String,
+    path: Option<String>,
+}
 
 pub async fn web_handler(
     State(state): State<ServerState>,
-    Path(app_name): Path<String>,
+    Path(AppPath { app_name, path }): Path<AppPath>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
 ) -> Response {
     println!("[WEB] Request received for app: {}", app_name);
 
-    let ipns_name = match App::find_by_name(&state.db_pool, &app_name).await {
-        Ok(app) => match app.ipns_name {
-            Some(name) => name,
-            None => {
-                eprintln!("[WEB] App has no IPNS name");
-                return (StatusCode::NOT_FOUND, "App has no IPNS name").into_response();
+    // Raw app segment of the URL, as sent by the client
+    let base_path = uri
+        .path()
+        .strip_prefix("/app/")
+        .and_then(|path| path.split('/').next())
+        .map(|app_segment| format!("/app/{}", app_segment))
+        .unwrap_or_default();
+    let path = path.as_deref().unwrap_or_default();
+
+    // `{app_name}@{deployment_id or alias}` pins a release of the app
+    if let Some((app_name, reference)) = app_name.split_once('@') {
+        return serve_permalink(
+            &state, app_name, reference, &base_path, path, &uri, &headers,
+        )
+        .await;
+    }
+
+    // Each app gets its own origin on its subdomain
+    let gateway_config = &state.server_settings.gateway;
+    if gateway_config.redirect_to_subdomains
+        && let Some(base_domain) = &gateway_config.base_domain
+        && is_subdomain_label(&app_name)
+    {
+        return Redirect::permanent(&subdomain_location(&app_name, base_domain, &uri))
+            .into_response();
+    }
+
+    serve_app(&state, &app_name, &base_path, path, &uri, &headers).await
+}
+
+/// Serves app subdomains and verified custom domains at their root, other hosts go on to the
+/// satellite routes.
+pub async fn host_dispatch(
+    State(state): State<ServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let hostname = match request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| request.uri().host())
+        .and_then(host_name)
+    {
+        Some(hostname) => hostname,
+        None => return next.run(request).await,
+    };
+
+    let subdomain_app = state
+        .server_settings
+        .gateway
+        .base_domain
+        .as_deref()
+        .and_then(|base_domain| subdomain_label(&hostname, &base_domain.to_ascii_lowercase()))
+        .map(str::to_string);
+    let app_name = match subdomain_app {
+        Some(app_name) => app_name,
+        None => match resolve_host(&state, &hostname).await {
+            Ok(Some(app_name)) => app_name,
+            Ok(None) => return next.run(request).await,
+            Err(e) => {
+                eprintln!("[WEB] Error in domain lookup of {}: {}", hostname, e);
+                return next.run(request).await;
             }
         },
-        Err(e) => {
+    };
+    println!(
+        "[WEB] Request received for domain {} of app: {}",
+        hostname, app_name
+    );
+
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    let path = match percent_decode_str(request.uri().path()).decode_utf8() {
+        Ok(path) => path.into_owned(),
+        Err(_) => return (StatusCode::BAD_REQUEST, "Invalid path").into_response(),
+    };
+
+    serve_app(
+        &state,
+        &app_name,
+        "",
+        &path,
+        request.uri(),
+        request.headers(),
+    )
+    .await
+}
+
+/// Subdomain URL of a `/app/{app_name}/...` request, keeping the scheme of the request.
+fn subdomain_location(app_name: &str, base_domain: &str, uri: &Uri) -> String {
+    let site_path = uri
+        .path()
+        .strip_prefix("/app/")
+        .and_then(|path| path.split_once('/'))
+        .map(|(_, site_path)| site_path)
+        .unwrap_or_default();
+
+    match uri.query() {
+        Some(query) => format!("//{}.{}/{}?{}", app_name, base_domain, site_path, query),
+        None => format!("//{}.{}/{}", app_name, base_domain, site_path),
+    }
+}
+
+/// Serves a path of the site an app currently publishes.
+async fn serve_app(
+    state: &ServerState,
+    app_name: &str,
+    base_path: &str,
+    path: &str,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Response {
+    let app = match resolve_app(state, app_name).await {
+        Ok(app) => app,
+        Err(AppResolveError::NotFound(e)) => {
             eprintln!("[WEB] App not found: {}", e);
             return (StatusCode::NOT_FOUND, "App not found").into_response();
         }
+        Err(AppResolveError::Unavailable(e)) => {
+            eprintln!("[WEB] App resolution error: {}", e);
+            return (StatusCode::BAD_GATEWAY, "App resolution error").into_response();
+        }
     };
+    println!("[WEB] App \"{}\" found. CID: {}", app_name, app.current_cid);
 
-    println!("[WEB] App \"{}\" found. IPNS name: {}", app_name, ipns_name);
+    let content_root = match &app.ipns_name {
+        Some(ipns_name) => format!("/ipns/{}", ipns_name),
+        None => format!("/ipfs/{}", app.current_cid),
+    };
+    let root = SiteRoot {
+        cid: &app.current_cid,
+        content_root,
+        cache_control: &state.server_settings.gateway.ipns_cache_control,
+        base_path: base_path.to_string(),
+        rules: app.site_rules.as_ref(),
+    };
+    serve_authorized(state, app_name, &app.access, &root, path, uri, headers).await
+}
 
-    let client = Client::new();
-    let ipns_url = format!(
-        "{}/api/v0/name/resolve?arg={}",
-        state.server_settings.server.ipfs_host, ipns_name
+pub async fn preview_handler(
+    State(state): State<ServerState>,
+    Path(PreviewPath {
+        deployment_id,
+        path,
+    }): Path<PreviewPath>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    println!(
+        "[WEB] Preview request received for deployment: {}",
+        deployment_id
     );
 
-    let res = match client.post(&ipns_url).send().await {
-        Ok(resp) => resp,
+    let deployment = match Deployment::find_by_id(&state.db_pool, &deployment_id).await {
+        Ok(deployment) => deployment,
         Err(e) => {
-            eprintln!("[WEB] IPNS error: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, "IPNS error").into_response();
+            eprintln!("[WEB] Deployment not found: {}", e);
+            return (StatusCode::NOT_FOUND, "Deployment not found").into_response();
         }
     };
 
-    if !res.status().is_success() {
-        return (StatusCode::NOT_FOUND, "Name not found in IPNS").into_response();
+    // Only deployments whose content is still kept on the satellite can be previewed
+    if !deployment.is_servable() {
+        return (StatusCode::NOT_FOUND, "Deployment not available").into_response();
     }
 
-    let cid = match res.json::<serde_json::Value>().await {
-        Ok(json) => json["Path"].clone().as_str().unwrap()[6..].to_string(),
+    let app = match find_deployment_app(&state, &deployment).await {
+        Ok(app) => app,
+        Err(response) => return response,
+    };
+    let base_path = format!("/preview/{}", deployment.id);
+    let root = deployment_root(
+        &deployment,
+        &state.server_settings.gateway.immutable_cache_control,
+        &base_path,
+    );
+    serve_authorized(
+        &state,
+        &app.name,
+        &AppAccess::from(&app),
+        &root,
+        path.as_deref().unwrap_or_default(),
+        &uri,
+        &headers,
+    )
+    .await
+}
+
+/// Serves the site of a known deployment from its CID.
+pub async fn cid_handler(
+    State(state): State<ServerState>,
+    Path(CidPath { cid, path }): Path<CidPath>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    serve_cid(
+        &state,
+        &cid,
+        path.as_deref().unwrap_or_default(),
+        &uri,
+        &headers,
+    )
+    .await
+}
+
+/// Serves a path of a CID, as long as it is the content of a deployment kept on the satellite.
+pub(crate) async fn serve_cid(
+    state: &ServerState,
+    cid: &str,
+    path: &str,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Response {
+    println!("[WEB] Request received for CID: {}", cid);
+
+    // The satellite is no public gateway, unknown CIDs are not fetched from the network
+    let deployment = match Deployment::find_servable_by_cid(&state.db_pool, cid).await {
+        Ok(Some(deployment)) => deployment,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Content not found").into_response(),
         Err(e) => {
-            eprintln!("[WEB] IPNS JSON parse error: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, "IPNS JSON parse error").into_response();
+            eprintln!("[WEB] Error in deployment lookup of CID {}: {}", cid, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Deployment lookup error").into_response();
         }
     };
-    println!("[WEB] App \"{}\" found. CID: {}", app_name, cid);
 
-    let ipfs_url = format!(
-        "{}/api/v0/cat?arg={}",
-        state.server_settings.server.ipfs_host, cid
+    let app = match find_deployment_app(state, &deployment).await {
+        Ok(app) => app,
+        Err(response) => return response,
+    };
+    let base_path = format!("/ipfs/{}", cid);
+    let root = deployment_root(
+        &deployment,
+        &state.server_settings.gateway.immutable_cache_control,
+        &base_path,
     );
+    serve_authorized(
+        state,
+        &app.name,
+        &AppAccess::from(&app),
+        &root,
+        path,
+        uri,
+        headers,
+    )
+    .await
+}
 
-    let res = match client.post(&ipfs_url).send().await {
-        Ok(resp) => resp,
+/// Serves a release of an app pinned by its deployment id or one of its aliases.
+async fn serve_permalink(
+    state: &ServerState,
+    app_name: &str,
+    reference: &str,
+    base_path: &str,
+    path: &str,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Response {
+    let app = match App::find_by_name(&state.db_pool, &app_name.to_string()).await {
+        Ok(app) => app,
         Err(e) => {
-            eprintln!("[WEB] IPFS error: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, "IPFS error").into_response();
+            eprintln!("[WEB] App not found: {}", e);
+            return (StatusCode::NOT_FOUND, "App not found").into_response();
+        }
+    };
+
+    let deployment = match find_deployment_by_reference(&state.db_pool, &app, reference).await {
+        Ok(Some(deployment)) if deployment.is_servable() => deployment,
+        Ok(_) => return (StatusCode::NOT_FOUND, "Deployment not found").into_response(),
+        Err(e) => {
+            eprintln!("[WEB] Error in deployment lookup: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Deployment lookup error").into_response();
+        }
+    };
+    println!(
+        "[WEB] Release \"{}\" of app \"{}\" found. CID: {}",
+        reference, app_name, deployment.cid
+    );
+
+    // A deployment id always means the same content, an alias can be moved
+    let gateway_config = &state.server_settings.gateway;
+    let cache_control = if is_deployment_id(reference) {
+        &gateway_config.immutable_cache_control
+    } else {
+        &gateway_config.ipns_cache_control
+    };
+    let root = deployment_root(&deployment, cache_control, base_path);
+    serve_authorized(
+        state,
+        &app.name,
+        &AppAccess::from(&app),
+        &root,
+        path,
+        uri,
+        headers,
+    )
+    .await
+}
+
+/// Loads the app of a deployment, whose access mode applies to all its content.
+pub(crate) async fn find_deployment_app(
+    state: &ServerState,
+    deployment: &Deployment,
+) -> Result<App, Response> {
+    match App::find_by_id(&state.db_pool, &deployment.app_id.to_string()).await {
+        Ok(app) => Ok(app),
+        Err(e) => {
+            eprintln!("[WEB] App of deployment {} not found: {}", deployment.id, e);
+            Err((StatusCode::NOT_FOUND, "App not found").into_response())
         }
+    }
+}
+
+/// Site root of a deployment, addressed by its CID.
+fn deployment_root<'a>(
+    deployment: &'a Deployment,
+    cache_control: &'a str,
+    base_path: &str,
+) -> SiteRoot<'a> {
+    SiteRoot {
+        cid: &deployment.cid,
+        content_root: format!("/ipfs/{}", deployment.cid),
+        cache_control,
+        base_path: base_path.to_string(),
+        rules: deployment.site_rules.as_ref().map(|rules| &rules.0),
+    }
+}
+
+/// Serves a site once the visitor is let in the app, keeping private apps out of shared caches.
+async fn serve_authorized(
+    state: &ServerState,
+    app_name: &str,
+    access: &AppAccess,
+    root: &SiteRoot<'_>,
+    path: &str,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Response {
+    let session_cookie = match authorize(state, app_name, access, uri, headers).await {
+        Ok(session_cookie) => session_cookie,
+        Err(response) => return response,
+    };
+
+    let mut response = if access.mode == AccessMode::PUBLIC {
+        serve_site(state, root, path, uri, headers).await
+    } else {
+        let private_root = SiteRoot {
+            cid: root.cid,
+            content_root: root.content_root.clone(),
+            cache_control: PRIVATE_CACHE_CONTROL,
+            base_path: root.base_path.clone(),
+            rules: root.rules,
+        };
+        serve_site(state, &private_root, path, uri, headers).await
+    };
+    if let Some(session_cookie) = session_cookie {
+        response
+            .headers_mut()
+            .append(header::SET_COOKIE, session_cookie);
+    }
+
+    response
+}
+
+/// Serves a path of a site CID, with `index.html` standing for directories.
+async fn serve_site(
+    state: &ServerState,
+    root: &SiteRoot<'_>,
+    path: &str,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Response {
+    let segments = match path_segments(path) {
+        Some(segments) => segments,
+        None => return (StatusCode::BAD_REQUEST, "Invalid path").into_response(),
+    };
+
+    // The same path of the same CID is always the same response, no need to read it again
+    let etag = entity_tag(root.cid, &segments);
+    let ipfs_path = content_path(&root.content_root, &segments);
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| etag_matches(value, &etag));
+
+    let ipfs = state.ipfs.as_ref();
+    let request_path = format!("/{}", segments.join("/"));
+
+    // Forced rules apply even over existing files, the others only to missing paths
+    let forced = root
+        .rules
+        .and_then(|rules| rules.find_redirect(&request_path, true));
+    let mut response = match forced {
+        Some(rule_match) => apply_rule(ipfs, root, &rule_match, uri).await,
+        None => match resolve_site_path(ipfs, root.cid, &segments).await {
+            Ok(SiteTarget::Directory { .. }) if !uri.path().ends_with('/') => {
+                // Relative links of the index only resolve inside the directory with a trailing slash
+                let location = match uri.query() {
+                    Some(query) => format!("{}/?{}", uri.path(), query),
+                    None => format!("{}/", uri.path()),
+                };
+                Redirect::permanent(&location).into_response()
+            }
+            Ok(target) => match index_file(ipfs, target).await {
+                // Only once the file is known to exist, missing paths stay 404
+                Ok(file) if not_modified => not_modified_response(&file),
+                Ok(file) => send_file(ipfs, &file, StatusCode::OK, headers).await,
+                Err(e) => missing_path_response(ipfs, root, &request_path, uri, e).await,
+            },
+            Err(e) => missing_path_response(ipfs, root, &request_path, uri, e).await,
+        },
+    };
+
+    if matches!(
+        response.status(),
+        StatusCode::OK | StatusCode::PARTIAL_CONTENT | StatusCode::NOT_MODIFIED
+    ) {
+        let response_headers = response.headers_mut();
+        response_headers.insert(header::ETAG, HeaderValue::from_str(&etag).unwrap());
+        response_headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_str(root.cache_control).unwrap(),
+        );
+        response_headers.insert(X_IPFS_PATH, HeaderValue::from_str(&ipfs_path).unwrap());
+    }
+    if let Some(rules) = root.rules {
+        for (name, value) in rules.headers_for(&request_path) {
+            // Rules are validated at deploy time
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+    }
+
+    response
+}
+
+/// Falls back on the shadowed rules, then on the not found page of the site.
+async fn missing_path_response(
+    ipfs: &dyn IpfsBackend,
+    root: &SiteRoot<'_>,
+    request_path: &str,
+    uri: &Uri,
+    error: IpfsError,
+) -> Response {
+    if !matches!(error, IpfsError::NotFound(_)) {
+        return ipfs_error_response(error);
+    }
+    let rules = match root.rules {
+        Some(rules) => rules,
+        None => return ipfs_error_response(error),
+    };
+
+    if let Some(rule_match) = rules.find_redirect(request_path, false) {
+        return apply_rule(ipfs, root, &rule_match, uri).await;
+    }
+    match &rules.not_found_page {
+        Some(page) => serve_rewrite(ipfs, root, page, StatusCode::NOT_FOUND).await,
+        None => ipfs_error_response(error),
+    }
+}
+
+/// Redirects to the rule target, or serves it in place for rewrites and not found pages.
+async fn apply_rule(
+    ipfs: &dyn IpfsBackend,
+    root: &SiteRoot<'_>,
+    rule_match: &RuleMatch<'_>,
+    uri: &Uri,
+) -> Response {
+    match rule_match.rule.status {
+        200 => serve_rewrite(ipfs, root, &rule_match.target, StatusCode::OK).await,
+        404 => serve_rewrite(ipfs, root, &rule_match.target, StatusCode::NOT_FOUND).await,
+        status => {
+            // Site paths are relative to where the site is mounted on the gateway
+            let mut location = if rule_match.target.starts_with('/') {
+                format!("{}{}", root.base_path, rule_match.target)
+            } else {
+                rule_match.target.clone()
+            };
+            if let Some(query) = uri.query()
+                && !location.contains('?')
+            {
+                location = format!("{}?{}", location, query);
+            }
+
+            Response::builder()
+                .status(StatusCode::from_u16(status).unwrap_or(StatusCode::MOVED_PERMANENTLY))
+                .header(
+                    header::LOCATION,
+                    utf8_percent_encode(&location, LOCATION_ENCODE_SET).to_string(),
+                )
+                .body(Body::empty())
+                .unwrap()
+        }
+    }
+}
+
+/// Serves another path of the site under the requested URL.
+async fn serve_rewrite(
+    ipfs: &dyn IpfsBackend,
+    root: &SiteRoot<'_>,
+    target: &str,
+    status: StatusCode,
+) -> Response {
+    // Queries of rewrite targets have no meaning for static files
+    let target_path = target.split('?').next().unwrap_or_default();
+    let segments = match path_segments(target_path) {
+        Some(segments) => segments,
+        None => return (StatusCode::NOT_FOUND, "Not found").into_response(),
     };
 
-    if !res.status().is_success() {
-        return (StatusCode::NOT_FOUND, "CID not found in IPFS").into_response();
+    let file = match resolve_site_path(ipfs, root.cid, &segments).await {
+        Ok(target) => index_file(ipfs, target).await,
+        Err(e) => Err(e),
+    };
+    match file {
+        Ok(file) => send_file(ipfs, &file, status, &HeaderMap::new()).await,
+        Err(e) => ipfs_error_response(e),
     }
+}
 
-    let body = Body::from(res.text().await.unwrap_or_default());
+/// The file itself, or the `index.html` of a directory.
+async fn index_file(ipfs: &dyn IpfsBackend, target: SiteTarget) -> Result<SiteFile, IpfsError> {
+    match target {
+        SiteTarget::File(file) => Ok(file),
+        SiteTarget::Directory { ipfs_path, roots } => {
+            find_file(ipfs, &ipfs_path, &roots, "index.html").await
+        }
+    }
+}
 
+/// Tells the client its copy of a file is current, the caller adding the same headers as to
+/// the full response.
+fn not_modified_response(file: &SiteFile) -> Response {
     Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "text/html")
-        .body(body)
+        .status(StatusCode::NOT_MODIFIED)
+        .header(X_IPFS_ROOTS, file.roots.join(","))
+        .body(Body::empty())
         .unwrap()
 }
+
+/// Streams a file, honoring `Range` requests on successful responses.
+async fn send_file(
+    ipfs: &dyn IpfsBackend,
+    file: &SiteFile,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> Response {
+    let content_type = match guess_content_type(&file.name) {
+        Some(content_type) => content_type,
+        None => match cat_to_vec(ipfs, &file.ipfs_path, Some(SNIFF_LENGTH as u64)).await {
+            Ok(head) => sniff_content_type(&head),
+            Err(e) => return ipfs_error_response(e),
+        },
+    };
+
+    let range = match headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(value) if status == StatusCode::OK => parse_range(value, file.size),
+        _ => ByteRange::Full,
+    };
+    let (status, offset, length) = match range {
+        ByteRange::Full => (status, 0, file.size),
+        ByteRange::Partial { start, end } => (StatusCode::PARTIAL_CONTENT, start, end - start + 1),
+        ByteRange::Unsatisfiable => {
+            return Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", file.size))
+                .body(Body::empty())
+                .unwrap();
+        }
+    };
+
+    // The whole file is read without a length so the backend streams it in one go
+    let read_length = (status == StatusCode::PARTIAL_CONTENT).then_some(length);
+    let stream = match ipfs.cat(&file.ipfs_path, offset, read_length).await {
+        Ok(stream) => stream,
+        Err(e) => return ipfs_error_response(e),
+    };
+
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_LENGTH, length)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(X_IPFS_ROOTS, file.roots.join(","));
+    if status == StatusCode::PARTIAL_CONTENT {
+        response = response.header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", offset, offset + length - 1, file.size),
+        );
+    }
+
+    response.body(Body::from_stream(stream)).unwrap()
+}
+
+pub(crate) fn ipfs_error_response(error: IpfsError) -> Response {
+    match error {
+        IpfsError::NotFound(path) => {
+            println!("[WEB] Path not found: {}", path);
+            (StatusCode::NOT_FOUND, "Not found").into_response()
+        }
+        IpfsError::Backend(e) => {
+            eprintln!("[WEB] IPFS error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "IPFS error").into_response()
+        }
+        IpfsError::Unsupported(e) => {
+            eprintln!("[WEB] IPFS backend limitation: {}", e);
+            (
+                StatusCode::NOT_IMPLEMENTED,
+                "Not supported by the IPFS backend",
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::{
+    access::{PRIVATE_CACHE_CONTROL, authorize},
+    content::etag_matches,
+    routes::gateway::{X_IPFS_PATH, find_deployment_app, ipfs_error_response, serve_cid},
+};
+use kc_core::{
+    access::AppAccess,
+    models::{app::AccessMode, deployment::Deployment},
+    server::ServerState,
+};
+
+const RAW_CONTENT_TYPE: &str = "application/vnd.ipld.raw";
+const CAR_CONTENT_TYPE: &str = "application/vnd.ipld.car";
+
+/// Verifiable response formats, which clients check against the CID themselves.
+#[derive(Clone, Copy)]
+enum TrustlessFormat {
+    Raw,
+    Car,
+}
+
+impl TrustlessFormat {
+    fn from_query(format: &str) -> Option<Self> {
+        match format {
+            "raw" => Some(TrustlessFormat::Raw),
+            "car" => Some(TrustlessFormat::Car),
+            _ => None,
+        }
+    }
+
+    fn from_accept(accept: &str) -> Option<Self> {
+        accept
+            .split(',')
+            .map(|media_range| media_range.split(';').next().unwrap_or_default().trim())
+            .find_map(|media_type| match media_type {
+                RAW_CONTENT_TYPE => Some(TrustlessFormat::Raw),
+                CAR_CONTENT_TYPE => Some(TrustlessFormat::Car),
+                _ => None,
+            })
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            TrustlessFormat::Raw => RAW_CONTENT_TYPE,
+            TrustlessFormat::Car => "application/vnd.ipld.car; version=1",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            TrustlessFormat::Raw => "bin",
+            TrustlessFormat::Car => "car",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TrustlessQuery {
+    format: Option<String>,
+}
+
+/// Serves a block or a CAR export of a CID stored by the satellite, or the deployed site when
+/// no verifiable format is asked for.
+pub async fn ipfs_handler(
+    State(state): State<ServerState>,
+    Path(cid): Path<String>,
+    Query(query): Query<TrustlessQuery>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    if cid.is_empty() || !cid.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return (StatusCode::BAD_REQUEST, "Invalid CID").into_response();
+    }
+
+    // The format parameter wins over the Accept header
+    let format = match query.format.as_deref() {
+        Some(format) => match TrustlessFormat::from_query(format) {
+            Some(format) => format,
+            None => return (StatusCode::BAD_REQUEST, "Unsupported format").into_response(),
+        },
+        None => match headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(TrustlessFormat::from_accept)
+        {
+            Some(format) => format,
+            None => return serve_cid(&state, &cid, "", &uri, &headers).await,
+        },
+    };
+    println!(
+        "[WEB] Trustless request received for CID {} ({})",
+        cid,
+        format.extension()
+    );
+
+    // Deployments of private apps stay behind their access mode in verifiable formats too
+    let (access_mode, session_cookie) = match Deployment::find_servable_by_cid(&state.db_pool, &cid)
+        .await
+    {
+        Ok(Some(deployment)) => {
+            let app = match find_deployment_app(&state, &deployment).await {
+                Ok(app) => app,
+                Err(response) => return response,
+            };
+            let access = AppAccess::from(&app);
+            match authorize(&state, &app.name, &access, &uri, &headers).await {
+                Ok(session_cookie) => (access.mode, session_cookie),
+                Err(response) => return response,
+            }
+        }
+        Ok(None) => (AccessMode::PUBLIC, None),
+        Err(e) => {
+            eprintln!("[WEB] Error in deployment lookup of CID {}: {}", cid, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Deployment lookup error").into_response();
+        }
+    };
+
+    let etag = format!("\"{}.{}\"", cid, format.extension());
+    let cache_control = match access_mode {
+        AccessMode::PUBLIC => state
+            .server_settings
+            .gateway
+            .immutable_cache_control
+            .as_str(),
+        _ => PRIVATE_CACHE_CONTROL,
+    };
+    if let Some(value) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        && etag_matches(value, &etag)
+    {
+        return Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, &etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .header(header::VARY, "Accept")
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    let stream = match format {
+        TrustlessFormat::Raw => state.ipfs.block_get(&cid).await,
+        TrustlessFormat::Car => state.ipfs.dag_export(&cid).await,
+    };
+    let stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => return ipfs_error_response(e),
+    };
+
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.{}\"", cid, format.extension()),
+        )
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(header::ETAG, &etag)
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::VARY, "Accept")
+        .header(X_IPFS_PATH, format!("/ipfs/{}", cid))
+        .body(Body::from_stream(stream))
+        .unwrap();
+    if let Some(session_cookie) = session_cookie {
+        response
+            .headers_mut()
+            .append(header::SET_COOKIE, session_cookie);
+    }
+
+    response
+}
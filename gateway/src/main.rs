@@ -1,7 +1,14 @@
-use axum::{Router, routing::get};
+use axum::{Router, middleware, routing::get};
 use kc_core::{
     database::create_db_pool,
+    events::{create_event_bus, create_node_event_bus, start_event_listener},
+    ipfs::create_ipfs_client,
+    jobs::start_workers,
     models::query::build_schema,
+    node::start_health_checker,
+    replication::start_reconciler,
+    reputation::start_reputation_updater,
+    retention::start_garbage_collector,
     server::{ServerSettings, ServerState},
 };
 use std::collections::HashMap;
@@ -26,16 +33,40 @@ async fn main() {
         }
     };
 
+    let ipfs = match create_ipfs_client(&settings.server.ipfs_backend, &settings.server.ipfs_host) {
+        Ok(ipfs) => ipfs,
+        Err(e) => {
+            panic!("Failed to create IPFS client: {}", e);
+        }
+    };
+
+    let event_bus = create_event_bus();
+    if let Err(e) = start_event_listener(&db_pool, event_bus.clone()).await {
+        panic!("Failed to listen to deployment events: {}", e);
+    }
+
     let graphql_schema = build_schema();
 
     let server_state: ServerState = ServerState {
         server_settings: settings.clone(),
         app_registry: Arc::new(Mutex::new(HashMap::new())),
+        domain_registry: Arc::new(Mutex::new(HashMap::new())),
         db_pool: db_pool,
         redis_client: redis_client,
+        ipfs,
+        event_bus,
+        node_event_bus: create_node_event_bus(),
         graphql_schema: graphql_schema,
     };
 
+    if let Err(e) = start_workers(server_state.clone()).await {
+        panic!("Failed to start job workers: {}", e);
+    }
+    start_reconciler(server_state.clone());
+    start_garbage_collector(server_state.clone());
+    start_health_checker(server_state.clone());
+    start_reputation_updater(server_state.clone());
+
     let app: Router = Router::new()
         .route("/", get(root_handler))
         .nest("/api/user", api_user::create_user_router())
@@ -44,6 +75,10 @@ async fn main() {
         .nest("/api/app", api_app::create_router())
         .nest("/api", api_graphql::create_router())
         .merge(web_server::create_router())
+        .layer(middleware::from_fn_with_state(
+            server_state.clone(),
+            web_server::routes::gateway::host_dispatch,
+        ))
         .with_state(server_state);
 
     let addr: SocketAddr = format!("{}:{}", settings.server.host, settings.server.port)
@@ -0,0 +1,3 @@
+ALTER TABLE deployments
+    DROP COLUMN IF EXISTS total_size,
+    DROP COLUMN IF EXISTS file_count;
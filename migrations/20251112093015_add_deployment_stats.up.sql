@@ -0,0 +1,3 @@
+ALTER TABLE deployments
+    ADD COLUMN file_count BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN total_size BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,15 @@
+ALTER TABLE deployments DROP COLUMN IF EXISTS source_deployment_id;
+
+UPDATE deployments SET status = 'DEPLOYED' WHERE status = 'SUPERSEDED';
+ALTER TYPE deployment_status RENAME TO deployment_status_old;
+CREATE TYPE deployment_status AS ENUM (
+    'PENDING',
+    'PUBLISHING',
+    'DEPLOYED',
+    'FAILED'
+);
+ALTER TABLE deployments
+    ALTER COLUMN status DROP DEFAULT,
+    ALTER COLUMN status TYPE deployment_status USING status::text::deployment_status,
+    ALTER COLUMN status SET DEFAULT 'PENDING';
+DROP TYPE deployment_status_old;
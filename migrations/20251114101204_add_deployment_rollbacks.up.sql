@@ -0,0 +1,4 @@
+ALTER TYPE deployment_status ADD VALUE IF NOT EXISTS 'SUPERSEDED'; -- Remplacé par un déploiement plus récent
+
+ALTER TABLE deployments
+    ADD COLUMN source_deployment_id UUID NULL REFERENCES deployments(id) ON DELETE SET NULL;
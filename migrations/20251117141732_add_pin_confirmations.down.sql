@@ -0,0 +1,4 @@
+ALTER TABLE deployments_nodes
+    DROP COLUMN IF EXISTS confirmed_at,
+    DROP COLUMN IF EXISTS requested_at,
+    DROP COLUMN IF EXISTS callback_token;
@@ -0,0 +1,4 @@
+ALTER TABLE deployments_nodes
+    ADD COLUMN callback_token VARCHAR(64) NULL,
+    ADD COLUMN requested_at TIMESTAMPTZ NULL,
+    ADD COLUMN confirmed_at TIMESTAMPTZ NULL;
@@ -0,0 +1,14 @@
+ALTER TABLE apps DROP COLUMN IF EXISTS replication_factor;
+
+UPDATE deployments_nodes SET status = 'FAILED' WHERE status = 'LOST';
+ALTER TYPE pin_status RENAME TO pin_status_old;
+CREATE TYPE pin_status AS ENUM (
+    'PINNING',
+    'PINNED',
+    'FAILED'
+);
+ALTER TABLE deployments_nodes
+    ALTER COLUMN status DROP DEFAULT,
+    ALTER COLUMN status TYPE pin_status USING status::text::pin_status,
+    ALTER COLUMN status SET DEFAULT 'PINNING';
+DROP TYPE pin_status_old;
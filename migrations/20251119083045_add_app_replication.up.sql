@@ -0,0 +1,3 @@
+ALTER TABLE apps ADD COLUMN replication_factor INTEGER NOT NULL DEFAULT 3 CHECK (replication_factor > 0);
+
+ALTER TYPE pin_status ADD VALUE IF NOT EXISTS 'LOST'; -- Le node a disparu, la copie n'est plus disponible
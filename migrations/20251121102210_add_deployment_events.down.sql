@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS notify_deployments_nodes_status ON deployments_nodes;
+DROP TRIGGER IF EXISTS notify_deployments_status ON deployments;
+DROP FUNCTION IF EXISTS notify_pin_status;
+DROP FUNCTION IF EXISTS notify_deployment_status;
@@ -0,0 +1,39 @@
+CREATE OR REPLACE FUNCTION notify_deployment_status()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' OR NEW.status IS DISTINCT FROM OLD.status THEN
+    PERFORM pg_notify('deployment_events', json_build_object(
+      'type', 'deployment',
+      'deployment_id', NEW.id,
+      'status', NEW.status
+    )::text);
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION notify_pin_status()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' OR NEW.status IS DISTINCT FROM OLD.status THEN
+    PERFORM pg_notify('deployment_events', json_build_object(
+      'type', 'pin',
+      'deployment_id', NEW.deployment_id,
+      'deployment_node_id', NEW.id,
+      'node_id', NEW.node_id,
+      'status', NEW.status
+    )::text);
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notify_deployments_status
+AFTER INSERT OR UPDATE ON deployments
+FOR EACH ROW
+EXECUTE PROCEDURE notify_deployment_status();
+
+CREATE TRIGGER notify_deployments_nodes_status
+AFTER INSERT OR UPDATE ON deployments_nodes
+FOR EACH ROW
+EXECUTE PROCEDURE notify_pin_status();
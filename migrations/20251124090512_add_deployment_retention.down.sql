@@ -0,0 +1,32 @@
+ALTER TABLE apps
+    DROP COLUMN IF EXISTS retention_days,
+    DROP COLUMN IF EXISTS retention_count;
+
+UPDATE deployments_nodes SET status = 'FAILED' WHERE status = 'UNPINNED';
+ALTER TYPE pin_status RENAME TO pin_status_old;
+CREATE TYPE pin_status AS ENUM (
+    'PINNING',
+    'PINNED',
+    'FAILED',
+    'LOST'
+);
+ALTER TABLE deployments_nodes
+    ALTER COLUMN status DROP DEFAULT,
+    ALTER COLUMN status TYPE pin_status USING status::text::pin_status,
+    ALTER COLUMN status SET DEFAULT 'PINNING';
+DROP TYPE pin_status_old;
+
+UPDATE deployments SET status = 'SUPERSEDED' WHERE status = 'ARCHIVED';
+ALTER TYPE deployment_status RENAME TO deployment_status_old;
+CREATE TYPE deployment_status AS ENUM (
+    'PENDING',
+    'PUBLISHING',
+    'DEPLOYED',
+    'FAILED',
+    'SUPERSEDED'
+);
+ALTER TABLE deployments
+    ALTER COLUMN status DROP DEFAULT,
+    ALTER COLUMN status TYPE deployment_status USING status::text::deployment_status,
+    ALTER COLUMN status SET DEFAULT 'PENDING';
+DROP TYPE deployment_status_old;
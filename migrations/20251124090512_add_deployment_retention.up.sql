@@ -0,0 +1,6 @@
+ALTER TABLE apps
+    ADD COLUMN retention_count INTEGER NULL CHECK (retention_count > 0),
+    ADD COLUMN retention_days INTEGER NULL CHECK (retention_days > 0);
+
+ALTER TYPE deployment_status ADD VALUE IF NOT EXISTS 'ARCHIVED'; -- Contenu retiré par la politique de rétention
+ALTER TYPE pin_status ADD VALUE IF NOT EXISTS 'UNPINNED'; -- Le node a retiré le contenu
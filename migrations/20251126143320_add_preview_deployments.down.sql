@@ -0,0 +1,15 @@
+UPDATE deployments SET status = 'SUPERSEDED' WHERE status = 'PREVIEW';
+ALTER TYPE deployment_status RENAME TO deployment_status_old;
+CREATE TYPE deployment_status AS ENUM (
+    'PENDING',
+    'PUBLISHING',
+    'DEPLOYED',
+    'FAILED',
+    'SUPERSEDED',
+    'ARCHIVED'
+);
+ALTER TABLE deployments
+    ALTER COLUMN status DROP DEFAULT,
+    ALTER COLUMN status TYPE deployment_status USING status::text::deployment_status,
+    ALTER COLUMN status SET DEFAULT 'PENDING';
+DROP TYPE deployment_status_old;
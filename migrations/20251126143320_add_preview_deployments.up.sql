@@ -0,0 +1 @@
+ALTER TYPE deployment_status ADD VALUE IF NOT EXISTS 'PREVIEW'; -- En ligne sur l'URL de prévisualisation uniquement
@@ -0,0 +1 @@
+DROP INDEX idx_deployments_live_app_id;
@@ -0,0 +1,10 @@
+-- Ne garde que le déploiement en ligne le plus récent de chaque app
+UPDATE deployments SET status = 'SUPERSEDED'
+WHERE status = 'DEPLOYED'
+    AND id NOT IN (
+        SELECT DISTINCT ON (app_id) id FROM deployments
+        WHERE status = 'DEPLOYED'
+        ORDER BY app_id, created_at DESC, id DESC
+    );
+
+CREATE UNIQUE INDEX idx_deployments_live_app_id ON deployments(app_id) WHERE status = 'DEPLOYED'; -- Un seul déploiement en ligne par app
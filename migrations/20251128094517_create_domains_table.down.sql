@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS set_domains_updated_at ON domains;
+DROP TABLE IF EXISTS domains;
@@ -0,0 +1,16 @@
+CREATE TABLE domains (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    app_id UUID NOT NULL REFERENCES apps(id) ON DELETE CASCADE,
+    hostname VARCHAR(255) UNIQUE NOT NULL,
+    verification_token VARCHAR(255) NOT NULL, -- Valeur attendue sur le fichier well-known ou l'enregistrement TXT
+    verified_at TIMESTAMPTZ NULL,             -- NULL tant que la propriété du domaine n'est pas prouvée
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_domains_app_id ON domains(app_id);
+
+CREATE TRIGGER set_domains_updated_at
+BEFORE UPDATE ON domains
+FOR EACH ROW
+EXECUTE PROCEDURE trigger_set_updated_at();
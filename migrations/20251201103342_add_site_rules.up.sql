@@ -0,0 +1,2 @@
+ALTER TABLE deployments
+    ADD COLUMN site_rules JSONB NULL; -- Redirections, en-têtes et page 404 compilés depuis _redirects, _headers et keyston.toml
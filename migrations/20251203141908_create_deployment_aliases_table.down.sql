@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS set_deployment_aliases_updated_at ON deployment_aliases;
+DROP TABLE IF EXISTS deployment_aliases;
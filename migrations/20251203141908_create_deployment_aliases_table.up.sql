@@ -0,0 +1,16 @@
+CREATE TABLE deployment_aliases (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    app_id UUID NOT NULL REFERENCES apps(id) ON DELETE CASCADE,
+    deployment_id UUID NOT NULL REFERENCES deployments(id) ON DELETE CASCADE,
+    name VARCHAR(64) NOT NULL, -- Nom de version, ex. v1.4.0 ou staging
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(app_id, name)
+);
+
+CREATE INDEX idx_deployment_aliases_deployment_id ON deployment_aliases(deployment_id);
+
+CREATE TRIGGER set_deployment_aliases_updated_at
+BEFORE UPDATE ON deployment_aliases
+FOR EACH ROW
+EXECUTE PROCEDURE trigger_set_updated_at();
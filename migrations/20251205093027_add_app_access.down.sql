@@ -0,0 +1,5 @@
+ALTER TABLE apps
+    DROP COLUMN access_password_hash,
+    DROP COLUMN access_mode;
+
+DROP TYPE access_mode;
@@ -0,0 +1,10 @@
+CREATE TYPE access_mode AS ENUM (
+    'PUBLIC', -- Servie à tous
+    'PASSWORD', -- Mot de passe demandé en authentification basique
+    'TOKEN', -- Liens signés émis par l'équipe
+    'TEAM' -- Membres de l'équipe connectés
+);
+
+ALTER TABLE apps
+    ADD COLUMN access_mode access_mode NOT NULL DEFAULT 'PUBLIC',
+    ADD COLUMN access_password_hash VARCHAR(255) NULL; -- Haché argon2, seulement en mode PASSWORD
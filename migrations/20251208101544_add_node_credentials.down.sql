@@ -0,0 +1,3 @@
+ALTER TABLE nodes
+    DROP COLUMN secret_rotated_at,
+    DROP COLUMN secret;
@@ -0,0 +1,3 @@
+ALTER TABLE nodes
+    ADD COLUMN secret VARCHAR(64) NULL, -- Clé HMAC des requêtes signées du nœud, NULL si révoquée
+    ADD COLUMN secret_rotated_at TIMESTAMPTZ NULL;
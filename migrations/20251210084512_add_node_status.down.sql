@@ -0,0 +1,7 @@
+DROP TABLE node_status_changes;
+
+ALTER TABLE nodes
+    DROP COLUMN status_changed_at,
+    DROP COLUMN status;
+
+DROP TYPE node_status;
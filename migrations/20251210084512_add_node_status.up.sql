@@ -0,0 +1,20 @@
+CREATE TYPE node_status AS ENUM (
+    'ONLINE', -- Battement de cœur récent
+    'DEGRADED', -- Battement de cœur en retard
+    'OFFLINE' -- Plus de battement de cœur
+);
+
+ALTER TABLE nodes
+    ADD COLUMN status node_status NOT NULL DEFAULT 'OFFLINE',
+    ADD COLUMN status_changed_at TIMESTAMPTZ NULL;
+
+CREATE TABLE node_status_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    node_id UUID NOT NULL REFERENCES nodes(id) ON DELETE CASCADE,
+    status node_status NOT NULL,
+    previous_status node_status NOT NULL,
+    last_seen TIMESTAMPTZ NULL, -- Dernier battement de cœur connu au moment du changement
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_node_status_changes_node_id ON node_status_changes(node_id, created_at DESC);
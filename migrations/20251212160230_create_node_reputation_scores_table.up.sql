@@ -0,0 +1,9 @@
+CREATE TABLE node_reputation_scores (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    node_id UUID NOT NULL REFERENCES nodes(id) ON DELETE CASCADE,
+    score DOUBLE PRECISION NOT NULL,
+    breakdown JSONB NOT NULL, -- Valeur, poids et nombre d'échantillons de chaque critère
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_node_reputation_scores_node_id ON node_reputation_scores(node_id, created_at DESC);
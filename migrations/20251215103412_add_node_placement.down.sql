@@ -0,0 +1,5 @@
+ALTER TABLE teams
+    DROP COLUMN pin_on_own_nodes,
+    DROP COLUMN pinning_regions;
+ALTER TABLE nodes
+    DROP COLUMN region;
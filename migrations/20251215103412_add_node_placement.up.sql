@@ -0,0 +1,5 @@
+ALTER TABLE nodes
+    ADD COLUMN region VARCHAR(64) NULL; -- Région déclarée par le propriétaire, pour répartir les réplicas
+ALTER TABLE teams
+    ADD COLUMN pinning_regions TEXT[] NOT NULL DEFAULT '{}', -- Régions autorisées pour les réplicas des apps de l'équipe, vide pour toutes
+    ADD COLUMN pin_on_own_nodes BOOLEAN NOT NULL DEFAULT FALSE; -- Ne répliquer que sur les nœuds de l'équipe
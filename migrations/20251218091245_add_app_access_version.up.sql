@@ -0,0 +1,2 @@
+ALTER TABLE apps
+    ADD COLUMN access_version INTEGER NOT NULL DEFAULT 0; -- Incrémentée à chaque changement d'accès, invalide les sessions et liens déjà émis
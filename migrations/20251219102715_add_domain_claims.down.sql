@@ -0,0 +1,11 @@
+-- Ne garde que le domaine vérifié, sinon la plus ancienne revendication de chaque nom d'hôte
+DELETE FROM domains
+WHERE id NOT IN (
+    SELECT DISTINCT ON (hostname) id FROM domains
+    ORDER BY hostname, verified_at IS NULL, created_at ASC, id ASC
+);
+
+ALTER TABLE domains DROP CONSTRAINT IF EXISTS domains_app_id_hostname_key;
+DROP INDEX IF EXISTS idx_domains_verified_hostname;
+
+ALTER TABLE domains ADD CONSTRAINT domains_hostname_key UNIQUE (hostname);
@@ -0,0 +1,7 @@
+-- Seul un domaine vérifié réserve son nom d'hôte, plusieurs apps peuvent le revendiquer en attendant
+ALTER TABLE domains DROP CONSTRAINT domains_hostname_key;
+
+CREATE UNIQUE INDEX idx_domains_verified_hostname ON domains(hostname) WHERE verified_at IS NOT NULL;
+
+ALTER TABLE domains
+ADD CONSTRAINT domains_app_id_hostname_key UNIQUE (app_id, hostname); -- Une seule revendication par app